@@ -0,0 +1,82 @@
+//          Copyright Andreas Wass 2004 - 2020.
+// Distributed under the Boost Software License, Version 1.0.
+//    (See accompanying file LICENSE or copy at
+//          https://www.boost.org/LICENSE_1_0.txt)
+
+/// BitReader reads bit fields, most significant bit first,
+/// from borrowed data.
+///
+/// Reading never changes the data the reader was created from.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    bit_position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Returns a BitReader positioned at the first bit of `data`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` the data to read from.
+    pub fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            bit_position: 0,
+        }
+    }
+
+    /// Reads `count` bits and advances the position.
+    ///
+    /// The first bit read ends up as the most significant bit of the
+    /// result. Returns `None`, without moving the position, if `count`
+    /// is greater than 32 or fewer than `count` bits remain.
+    pub fn read_bits(&mut self, count: u32) -> Option<u32> {
+        if count > 32 || count as usize > self.remaining_bits() {
+            return None;
+        }
+        let mut value = 0u32;
+        for _ in 0..count {
+            let byte = self.data[self.bit_position / 8];
+            let bit = (byte >> (7 - self.bit_position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.bit_position += 1;
+        }
+        Some(value)
+    }
+
+    /// Returns the number of bits read so far.
+    pub fn bit_position(&self) -> usize {
+        self.bit_position
+    }
+
+    /// Returns the number of bytes touched by the bits read so far.
+    ///
+    /// A partially read byte counts as a whole byte.
+    pub fn bytes_read(&self) -> usize {
+        self.bit_position.div_ceil(8)
+    }
+
+    /// Returns the number of bits left to read.
+    pub fn remaining_bits(&self) -> usize {
+        self.data.len() * 8 - self.bit_position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitReader;
+
+    #[test]
+    fn read_across_bytes() {
+        let data = [0b1010_1100u8, 0b0011_0000];
+        let mut reader = BitReader::new(&data);
+
+        assert_eq!(reader.read_bits(4), Some(0b1010));
+        assert_eq!(reader.read_bits(8), Some(0b1100_0011));
+        assert_eq!(reader.bit_position(), 12);
+        assert_eq!(reader.bytes_read(), 2);
+        assert_eq!(reader.read_bits(5), None);
+        assert_eq!(reader.read_bits(4), Some(0));
+        assert_eq!(reader.remaining_bits(), 0);
+    }
+}
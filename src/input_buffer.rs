@@ -4,12 +4,50 @@
 //    (See accompanying file LICENSE or copy at
 //          https://www.boost.org/LICENSE_1_0.txt)
 
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use core::convert::TryInto;
+
+use crate::bit_reader::BitReader;
+use crate::read_cursor::ReadCursor;
+
 #[derive(Debug)]
 pub enum AddError
 {
     Overflow
 }
 
+/// VarintError is returned by `read_varint` for data that
+/// can never become a valid varint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarintError
+{
+    /// The varint has more than 64 bits of value, or continues
+    /// past the 10 bytes a `u64` needs.
+    Overflow
+}
+
+/// OverflowPolicy selects what `push` and `push_multiple` do with
+/// data that doesn't fit.
+///
+/// All policies set the overflow flag when data is lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy
+{
+    /// Data that doesn't fit is not added and `push` returns an error.
+    #[default]
+    Refuse,
+    /// The oldest buffered data is dropped to make room for new data.
+    DropOldest,
+    /// Data that doesn't fit is silently dropped.
+    DropNewest,
+}
+
 /// InputBuffer is a byte buffer with a fixed capacity
 /// but dynamic lenth.
 /// Adding data will always grow `len()`
@@ -23,26 +61,177 @@ pub enum AddError
 pub struct InputBuffer<'a> {
     buffer: &'a mut [u8],
     next_input_pos: usize,
-    overflow: bool
+    overflow: bool,
+    overflow_count: usize,
+    bytes_dropped: usize,
+    policy: OverflowPolicy,
+    high_watermark: Option<(usize, fn())>,
+    high_watermark_armed: bool,
+    min_fill: usize,
+    peak_demand: usize,
+    occupancy: [u32; 8],
+    history: &'a mut [u8],
+    history_len: usize,
+    overflow_seq: u64,
+    last_overflow_seq: Option<u64>,
 }
 
 impl<'a> InputBuffer<'a> {
     fn write_area(&mut self) -> &mut [u8] {
-        return self.buffer.split_at_mut(self.next_input_pos).1;
+        self.buffer.split_at_mut(self.next_input_pos).1
+    }
+
+    /// Adds `count` bytes, already written to the write area, to the buffer.
+    ///
+    /// Callers check `count` against `remaining_capacity()` rather than
+    /// checking `len() + count` against capacity, since the subtraction
+    /// can't wrap even when `usize` is only 16 bits wide.
+    fn grow(&mut self, count: usize) {
+        debug_assert!(count <= self.remaining_capacity());
+        self.next_input_pos += count;
+        self.track_demand(self.next_input_pos);
+        self.update_high_watermark();
+    }
+
+    fn track_demand(&mut self, demand: usize) {
+        self.peak_demand = self.peak_demand.max(demand);
+    }
+
+    fn update_high_watermark(&mut self) {
+        check_high_watermark(self.next_input_pos, self.high_watermark, &mut self.high_watermark_armed);
+    }
+
+    fn record_overflow(&mut self, dropped: usize) {
+        self.record_overflow_with_demand(dropped, self.len().saturating_add(dropped));
+    }
+
+    /// Records an overflow for operations where the len that would
+    /// have been needed isn't len plus the dropped bytes.
+    fn record_overflow_with_demand(&mut self, dropped: usize, demand: usize) {
+        self.overflow = true;
+        self.overflow_count = self.overflow_count.saturating_add(1);
+        self.bytes_dropped = self.bytes_dropped.saturating_add(dropped);
+        self.last_overflow_seq = Some(self.overflow_seq);
+        self.track_demand(demand);
+    }
+
+    /// Consumes `count` bytes from the start of the buffer, moving any
+    /// remaining data to the start of the buffer.
+    ///
+    /// The consumed bytes are kept as rewind history.
+    fn remove_front(&mut self, count: usize) {
+        debug_assert!(count <= self.len());
+        self.record_history(count.min(self.len()));
+        self.shift_front(count);
+        self.update_high_watermark();
+    }
+
+    /// Drops `count` bytes like `remove_front` without keeping them as
+    /// rewind history, since they were never consumed, and without
+    /// updating the high watermark, which callers do once their whole
+    /// operation is done.
+    ///
+    /// Dropping any bytes forgets the rewind history, as it would no
+    /// longer be adjacent to the buffered data.
+    fn discard_front(&mut self, count: usize) {
+        if count != 0 {
+            self.history_len = 0;
+        }
+        self.shift_front(count);
+    }
+
+    /// Moves the data after the first `count` bytes to the start of
+    /// the buffer and reduces len by `count`.
+    fn shift_front(&mut self, count: usize) {
+        debug_assert!(count <= self.len());
+        let count = count.min(self.len());
+        let new_len = self.len() - count;
+        if new_len != 0 {
+            self.buffer.copy_within(core::ops::Range{start: count, end: self.next_input_pos}, 0);
+        }
+        self.next_input_pos = new_len;
+    }
+
+    /// Keeps the first `count` bytes, which are about to be removed,
+    /// as the most recent part of the rewind history.
+    fn record_history(&mut self, count: usize) {
+        let history_capacity = self.history.len();
+        if count >= history_capacity {
+            self.history.copy_from_slice(&self.buffer[count - history_capacity..count]);
+            self.history_len = history_capacity;
+            return;
+        }
+        let kept = self.history_len.min(history_capacity - count);
+        self.history.copy_within(self.history_len - kept..self.history_len, 0);
+        self.history[kept..kept + count].copy_from_slice(&self.buffer[..count]);
+        self.history_len = kept + count;
+    }
+
+    /// Returns an InputBuffer using the designated backing buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` the backing buffer used to provide storage for the InputBuffer.
+    pub fn new(buffer: &'a mut [u8]) -> InputBuffer<'a> {
+        InputBuffer::new_with_policy(buffer, OverflowPolicy::default())
     }
+
     /// Returns an InputBuffer using the designated backing buffer
+    /// and overflow policy.
     ///
     /// # Arguments
     ///
     /// * `buffer` the backing buffer used to provide storage for the InputBuffer.
-    pub fn new(buffer: &'a mut [u8]) -> InputBuffer {
+    /// * `policy` decides what happens to data that doesn't fit.
+    pub fn new_with_policy(buffer: &'a mut [u8], policy: OverflowPolicy) -> InputBuffer<'a> {
         InputBuffer {
-            buffer: buffer,
+            buffer,
             next_input_pos: 0,
             overflow: false,
+            overflow_count: 0,
+            bytes_dropped: 0,
+            policy,
+            high_watermark: None,
+            high_watermark_armed: false,
+            min_fill: 1,
+            peak_demand: 0,
+            occupancy: [0; 8],
+            history: &mut [],
+            history_len: 0,
+            overflow_seq: 0,
+            last_overflow_seq: None,
         }
     }
 
+    /// Returns an InputBuffer that can rewind recently consumed data.
+    ///
+    /// The first `rewind_capacity` bytes of `buffer` keep the most
+    /// recently consumed data, see `ib.rewind()`. The rest of `buffer`
+    /// provides storage for the InputBuffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` the backing buffer used to provide storage for the InputBuffer.
+    /// * `rewind_capacity` the number of consumed bytes to keep.
+    pub fn new_with_rewind(buffer: &'a mut [u8], rewind_capacity: usize) -> InputBuffer<'a> {
+        InputBuffer::new_with_rewind_and_policy(buffer, rewind_capacity, OverflowPolicy::default())
+    }
+
+    /// Returns an InputBuffer that can rewind recently consumed data,
+    /// using the designated overflow policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` the backing buffer used to provide storage for the InputBuffer.
+    /// * `rewind_capacity` the number of consumed bytes to keep.
+    /// * `policy` decides what happens to data that doesn't fit.
+    pub fn new_with_rewind_and_policy(buffer: &'a mut [u8], rewind_capacity: usize, policy: OverflowPolicy) -> InputBuffer<'a> {
+        let (history, buffer) = buffer.split_at_mut(rewind_capacity.min(buffer.len()));
+        let mut input_buffer = InputBuffer::new_with_policy(buffer, policy);
+        input_buffer.history = history;
+        input_buffer
+    }
+
     /// Push data to the back of the buffer.
     ///
     /// # Arguments
@@ -51,188 +240,1653 @@ impl<'a> InputBuffer<'a> {
     ///
     /// # Overflow behaviour
     ///
-    /// On overflow an error is returned, and `x.overflown()` will return true
+    /// On overflow `x.overflown()` will return true. With
+    /// `OverflowPolicy::Refuse` an error is returned, with
+    /// `OverflowPolicy::DropOldest` the first byte is dropped to
+    /// make room for `value` and with `OverflowPolicy::DropNewest`
+    /// `value` is dropped.
     pub fn push(&mut self, value: u8) -> Result<(), AddError> {
         if self.next_input_pos < self.capacity() {
-            self.buffer[self.next_input_pos as usize] = value;
-            self.next_input_pos += 1;
-            Ok(())
+            self.buffer[self.next_input_pos] = value;
+            self.grow(1);
+            return Ok(());
         }
-        else {
-            self.overflow = true;
-            Err(AddError::Overflow)
+
+        self.record_overflow(1);
+        match self.policy {
+            OverflowPolicy::Refuse => Err(AddError::Overflow),
+            OverflowPolicy::DropNewest => Ok(()),
+            OverflowPolicy::DropOldest => {
+                if self.capacity() > 0 {
+                    self.discard_front(1);
+                    self.buffer[self.next_input_pos] = value;
+                    self.grow(1);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Push data to the back of the buffer, returning the new len.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If the buffer is full `value` is handed back as the error, and
+    /// `x.overflown()` will return true. The overflow policy is not
+    /// consulted.
+    pub fn push_or_return(&mut self, value: u8) -> Result<usize, u8> {
+        if self.remaining_capacity() == 0 {
+            self.record_overflow(1);
+            return Err(value);
         }
+        self.buffer[self.next_input_pos] = value;
+        self.grow(1);
+        Ok(self.len())
     }
 
+    /// Pushes as much of `values` as fits to the back of the buffer.
+    ///
+    /// Returns the number of bytes added.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If not all of `values` fit `x.overflown()` will return true.
+    /// Pushing an empty slice never overflows.
+    ///
+    /// With `OverflowPolicy::DropOldest` buffered data is dropped from
+    /// the start of the buffer to make room for the end of `values`,
+    /// otherwise the part of `values` that doesn't fit is dropped.
     pub fn push_multiple(&mut self, values: &[u8]) -> usize {
-        let available_space = self.capacity() - self.len();
+        if values.is_empty() {
+            return 0;
+        }
+        let available_space = self.remaining_capacity();
         if values.len() <= available_space {
+            // copy_from_slice becomes a memcpy, which already copies the
+            // misaligned head and tail separately from an aligned bulk.
             self.write_area().split_at_mut(values.len()).0.copy_from_slice(values);
-            self.next_input_pos += values.len();
-            return values.len();
+            self.grow(values.len());
+            values.len()
+        }
+        else if self.policy == OverflowPolicy::DropOldest {
+            let kept = &values[values.len().saturating_sub(self.capacity())..];
+            self.discard_front(kept.len() - available_space);
+            self.write_area()[..kept.len()].copy_from_slice(kept);
+            self.grow(kept.len());
+            self.record_overflow(values.len() - available_space);
+            kept.len()
         }
         else {
             self.write_area().copy_from_slice(values.split_at(available_space).0);
-            self.overflow = true;
-            return available_space;
+            self.grow(available_space);
+            self.record_overflow(values.len() - available_space);
+            available_space
         }
     }
 
-    pub fn capacity(&self) -> usize {
-        self.buffer.len()
+    /// Pushes as much as fits of several slices, in order, to the back
+    /// of the buffer.
+    ///
+    /// Returns the total number of bytes added.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If not all of `inputs` fit `x.overflown()` will return true
+    /// and the part that doesn't fit is dropped, this counts as one
+    /// overflow.
+    pub fn push_vectored(&mut self, inputs: &[&[u8]]) -> usize {
+        let mut written = 0;
+        let mut dropped = 0usize;
+        for input in inputs {
+            let count = input.len().min(self.remaining_capacity());
+            self.write_area()[..count].copy_from_slice(&input[..count]);
+            self.grow(count);
+            written += count;
+            dropped = dropped.saturating_add(input.len() - count);
+        }
+        if dropped > 0 {
+            self.record_overflow(dropped);
+        }
+        written
     }
 
-    pub fn len(&self) -> usize {
-        self.next_input_pos
+    /// Pushes as much of `values` as fits, like `ib.push_multiple()`,
+    /// and reports whether all of it was added.
+    ///
+    /// Returns `Ok(written)` if all of `values` was added, otherwise
+    /// `Err((written, dropped))` where `dropped` is the number of bytes
+    /// of `values` that were not added.
+    pub fn push_multiple_detailed(&mut self, values: &[u8]) -> Result<usize, (usize, usize)> {
+        let written = self.push_multiple(values);
+        if written == values.len() {
+            Ok(written)
+        }
+        else {
+            Err((written, values.len() - written))
+        }
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.next_input_pos == 0
+    /// Pushes as much of `values` as fits to the back of the buffer
+    /// without ever marking an overflow.
+    ///
+    /// Returns the number of bytes added. Data that doesn't fit is
+    /// dropped regardless of the overflow policy, and neither the
+    /// overflow flag nor the overflow statistics are updated.
+    pub fn push_multiple_best_effort(&mut self, values: &[u8]) -> usize {
+        let written = values.len().min(self.remaining_capacity());
+        if written == 0 {
+            return 0;
+        }
+        self.write_area()[..written].copy_from_slice(&values[..written]);
+        self.grow(written);
+        written
     }
 
-    /// Returns true if an overflow has been detected
+    /// Pushes bytes from `iter` until it is exhausted or the buffer is full.
     ///
-    /// Use `ib.clear()` to clear the overflow flag.
-    pub fn overflown(&self) -> bool {
-        self.overflow
+    /// Returns the number of bytes added.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If the buffer fills up before `iter` is exhausted `x.overflown()`
+    /// will return true. Only the one byte taken from `iter` to detect
+    /// this counts as dropped, the rest of `iter` is left untouched.
+    pub fn extend_from_iter<I: IntoIterator<Item = u8>>(&mut self, iter: I) -> usize {
+        let mut iter = iter.into_iter();
+        let mut written = 0;
+        while written < self.remaining_capacity() {
+            match iter.next() {
+                Some(value) => {
+                    self.write_area()[written] = value;
+                    written += 1;
+                }
+                None => break,
+            }
+        }
+        self.grow(written);
+        if self.remaining_capacity() == 0 && iter.next().is_some() {
+            self.record_overflow(1);
+        }
+        written
     }
 
-    /// Resizes the buffer.
+    /// Pushes `value` to the back of the buffer `count` times.
     ///
-    /// This does *not* clear the overflow flag,
-    /// use `ib.clear()` for that.
+    /// Returns the number of bytes added.
     ///
-    /// If `new_size` is greater than capacity then
-    /// the value of capacity is used instead.
-    pub fn resize(&mut self, new_size: usize) {
-        self.next_input_pos = new_size.min(self.capacity());
+    /// # Overflow behaviour
+    ///
+    /// As many bytes as fit are added, if `count` exceeds the remaining
+    /// capacity `x.overflown()` will return true.
+    pub fn push_repeated(&mut self, value: u8, count: usize) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        let written = count.min(self.remaining_capacity());
+        self.write_area()[..written].fill(value);
+        self.grow(written);
+        if written < count {
+            self.record_overflow(count - written);
+        }
+        written
     }
 
-    /// Clears the buffer.
+    /// Pushes all of `values` or, on overflow, nothing at all.
+    fn push_all(&mut self, values: &[u8]) -> Result<(), AddError> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        if values.len() > self.remaining_capacity() {
+            self.record_overflow(values.len());
+            return Err(AddError::Overflow);
+        }
+        self.write_area()[..values.len()].copy_from_slice(values);
+        self.grow(values.len());
+        Ok(())
+    }
+
+    /// Pushes `value` encoded as an unsigned LEB128 varint.
     ///
-    /// This clears the overflow flag and
-    /// sets len to 0.
-    pub fn clear(&mut self) {
-        self.next_input_pos = 0;
-        self.overflow = false;
+    /// # Overflow behaviour
+    ///
+    /// If the encoded value doesn't fit nothing is added, an error is
+    /// returned and `x.overflown()` will return true.
+    pub fn push_varint(&mut self, mut value: u64) -> Result<(), AddError> {
+        let mut encoded = [0u8; 10];
+        let mut encoded_len = 0;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                encoded[encoded_len] = byte;
+                encoded_len += 1;
+                break;
+            }
+            encoded[encoded_len] = byte | 0x80;
+            encoded_len += 1;
+        }
+        self.push_all(&encoded[..encoded_len])
     }
 
-    /// Takes data from the start of the buffer, moving any
-    /// remaining data to the start of the buffer and decreases
-    /// len.
+    /// Pushes all `K` bytes of `values` to the back of the buffer.
     ///
-    /// This is the only way to retrieve data from the buffer.
+    /// # Overflow behaviour
     ///
-    /// # Arguments
+    /// If all of `values` doesn't fit nothing is added, an error is
+    /// returned and `x.overflown()` will return true.
+    pub fn push_array<const K: usize>(&mut self, values: [u8; K]) -> Result<(), AddError> {
+        self.push_all(&values)
+    }
+
+    /// Adds `count` zero bytes to the start of the buffer, moving the
+    /// buffered data towards the back.
     ///
-    /// * `output` The output buffer for data.
+    /// The new bytes can be filled in later using `ib.write_at()`.
     ///
-    /// # Examples
+    /// # Overflow behaviour
     ///
-    /// ```
-    /// extern crate uio_buffer;
-    /// use uio_buffer::input_buffer;
-    /// let mut backing_buffer = [0u8; 10];
-    /// let mut buffer = input_buffer::InputBuffer::new(&mut backing_buffer);
-    /// buffer.push_multiple(&[1,2,3,4,5]);
-    /// assert_eq!(buffer.len(), 5);
-    /// let mut read_buffer = [0u8; 3];
-    /// let consumed = buffer.consume(&mut read_buffer);
-    /// assert_eq!(consumed, 3);
-    /// assert_eq!(read_buffer, [1,2,3]);
-    /// assert_eq!(buffer.len(), 2);
-    /// // buffer contains [4,5]
-    /// ```
-    pub fn consume(&mut self, output: &mut [u8]) -> usize {
-        let bytes_to_consume = self.len().min(output.len());
-        if bytes_to_consume == 0 {
-            return 0;
+    /// If `count` exceeds the remaining capacity nothing is changed, an
+    /// error is returned and `x.overflown()` will return true.
+    pub fn make_front_room(&mut self, count: usize) -> Result<(), AddError> {
+        if count > self.remaining_capacity() {
+            self.record_overflow(count);
+            return Err(AddError::Overflow);
         }
+        let len = self.len();
+        self.buffer.copy_within(0..len, count);
+        self.buffer[..count].fill(0);
+        self.grow(count);
+        Ok(())
+    }
 
-        let split_input = self.buffer.split_at(bytes_to_consume);
-        let output_area = output.split_at_mut(bytes_to_consume).0;
-        let input_area = split_input.0;
-        output_area.copy_from_slice(input_area);
+    /// Inserts `values` into the buffered data at `offset`, moving
+    /// the data after `offset` towards the back.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If `values` doesn't fit nothing is changed, an error is
+    /// returned and `x.overflown()` will return true.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is greater than len.
+    pub fn insert_at(&mut self, offset: usize, values: &[u8]) -> Result<(), AddError> {
+        self.splice(offset..offset, values)
+    }
 
-        let new_len = self.len()-bytes_to_consume;
-        if new_len == 0 {
-            self.next_input_pos = 0;
-            return bytes_to_consume;
+    /// Replaces `range` of the buffered data with `replacement`.
+    ///
+    /// The data after `range` is moved to make room for, or close the
+    /// gap after, `replacement`.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If the result doesn't fit nothing is changed, an error is
+    /// returned and `x.overflown()` will return true.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is not within the buffered data.
+    pub fn splice(&mut self, range: core::ops::Range<usize>, replacement: &[u8]) -> Result<(), AddError> {
+        let len = self.len();
+        let removed = self.as_slice()[range.clone()].len();
+        if replacement.len() > removed && replacement.len() - removed > self.remaining_capacity() {
+            self.record_overflow_with_demand(replacement.len(), len - removed + replacement.len());
+            return Err(AddError::Overflow);
         }
-        self.buffer.copy_within(core::ops::Range{start: bytes_to_consume, end: self.next_input_pos}, 0);
-        self.next_input_pos = new_len;
-        return bytes_to_consume;
+        self.buffer.copy_within(range.end..len, range.start + replacement.len());
+        self.buffer[range.start..range.start + replacement.len()].copy_from_slice(replacement);
+        if replacement.len() > removed {
+            self.grow(replacement.len() - removed);
+        }
+        else {
+            self.resize(len - (removed - replacement.len()));
+        }
+        Ok(())
     }
-}
 
-mod tests {
-    use super::InputBuffer;
+    /// Pushes the buffered data of `other` to the back of the buffer.
+    ///
+    /// `other` is left unchanged. Returns the number of bytes added.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If all of the data doesn't fit nothing is added, an error is
+    /// returned and `x.overflown()` will return true.
+    pub fn append(&mut self, other: &InputBuffer) -> Result<usize, AddError> {
+        self.push_all(other.as_slice())?;
+        Ok(other.len())
+    }
 
-    #[test]
-    fn capacity_len_on_new() {
-        let mut buffer = [0u8; 10];
-        let target_len = buffer.len();
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
 
-        let input_buffer = InputBuffer::new(&mut buffer);
-        assert_eq!(input_buffer.capacity(), target_len);
-        assert_eq!(input_buffer.len(), 0);
+    pub fn len(&self) -> usize {
+        self.next_input_pos
     }
 
-    #[test]
-    fn len_increases_on_add() {
-        let mut buffer = [0u8; 10];
+    /// Returns `(len, capacity)`, the bytes in use and the bytes
+    /// of backing storage, in one call.
+    pub fn footprint(&self) -> (usize, usize) {
+        (self.len(), self.capacity())
+    }
 
-        {
-            let mut input_buffer = InputBuffer::new(&mut buffer);
-            input_buffer.push(10).unwrap();
-            assert_eq!(input_buffer.len(), 1);
+    /// Returns the number of bytes that can be added before
+    /// the buffer overflows.
+    pub fn remaining_capacity(&self) -> usize {
+        debug_assert!(self.len() <= self.capacity());
+        self.capacity().saturating_sub(self.len())
+    }
 
-            input_buffer.push(20).unwrap();
-            assert_eq!(input_buffer.len(), 2);
+    /// Returns the number of buffered bytes waiting to be consumed.
+    ///
+    /// This is the same as `ib.len()`.
+    pub fn backlog(&self) -> usize {
+        self.len()
+    }
+
+    /// Returns how full the buffer is, in permille of capacity.
+    ///
+    /// A buffer with zero capacity is always full and returns 1000.
+    pub fn backlog_ratio_permille(&self) -> u16 {
+        if self.capacity() == 0 {
+            return 1000;
         }
+        (self.len() as u64 * 1000 / self.capacity() as u64) as u16
+    }
 
-        assert_eq!(buffer[0], 10);
-        assert_eq!(buffer[1], 20);
+    /// Checks that `additional` more bytes can be added without overflowing.
+    ///
+    /// The buffer is not changed, an error is returned if
+    /// `additional` exceeds the remaining capacity.
+    pub fn try_reserve(&self, additional: usize) -> Result<(), AddError> {
+        if additional > self.remaining_capacity() {
+            return Err(AddError::Overflow);
+        }
+        Ok(())
     }
 
-    #[test]
-    fn detect_overflow()
-    {
-        let mut buffer = [0u8; 2];
+    pub fn is_empty(&self) -> bool {
+        self.next_input_pos == 0
+    }
 
-        {
-            let mut input_buffer = InputBuffer::new(&mut buffer);
-            input_buffer.push(10).unwrap();
-            input_buffer.push(20).unwrap();
+    /// Returns the buffered data as two slices.
+    ///
+    /// The buffered data is the first slice followed by the second.
+    /// The buffered data of an InputBuffer is always contiguous, so
+    /// the second slice is always empty.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        (self.as_slice(), &[])
+    }
 
-            assert!(!input_buffer.overflown());
-            assert!(input_buffer.push(30).is_err());
-            assert!(input_buffer.overflown());
+    /// Returns true if at least `count` bytes are buffered.
+    pub fn has_at_least(&self, count: usize) -> bool {
+        self.len() >= count
+    }
 
-            input_buffer.clear();
-            assert!(!input_buffer.overflown());
-            assert_eq!(input_buffer.len(), 0);
-        }
+    /// Sets the len at which `ib.ready()` starts returning true.
+    ///
+    /// The default minimum fill is 1 byte.
+    pub fn set_min_fill(&mut self, min_fill: usize) {
+        self.min_fill = min_fill;
+    }
 
-        assert_eq!(buffer[0], 10);
-        assert_eq!(buffer[1], 20);
+    /// Returns true if at least the minimum fill is buffered.
+    pub fn ready(&self) -> bool {
+        self.has_at_least(self.min_fill)
     }
 
-    #[test]
-    fn consume() {
-        let mut buffer = [0u8; 10];
-        let mut input_buffer = InputBuffer::new(&mut buffer);
+    /// Returns the buffered data.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer[..self.next_input_pos]
+    }
 
-        let mut consume_buffer = [0u8; 2];
+    /// Returns the unused part of the backing buffer.
+    ///
+    /// Data written here is added to the buffer with `ib.advance()`.
+    pub fn spare_capacity_mut(&mut self) -> &mut [u8] {
+        self.write_area()
+    }
 
-        input_buffer.push(1).unwrap();
-        assert_eq!(input_buffer.len(), 1);
-        let consumed = input_buffer.consume(&mut consume_buffer);
-        assert_eq!(consumed, 1);
-        assert_eq!(consume_buffer[0], 1);
-        assert_eq!(input_buffer.len(), 0);
+    /// Returns the number of padding bytes and the unused part of the
+    /// backing buffer starting at the first address aligned to `align`.
+    ///
+    /// The padding bytes skipped to reach alignment are zeroed, and must
+    /// be included when advancing past the returned slice, so that
+    /// `ib.advance(padding + n)` adds the padding and `n` written bytes.
+    /// An empty slice is returned if no aligned room exists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn aligned_spare_capacity_mut(&mut self, align: usize) -> (usize, &mut [u8]) {
+        let spare = self.write_area();
+        let padding = spare.as_ptr().align_offset(align).min(spare.len());
+        let (padding_area, aligned) = spare.split_at_mut(padding);
+        padding_area.fill(0);
+        (padding, aligned)
+    }
 
-        input_buffer.push(2).unwrap();
+    /// Adds `count` bytes, written through `ib.spare_capacity_mut()`,
+    /// to the buffer.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If `count` exceeds the remaining capacity nothing is added, an
+    /// error is returned and `x.overflown()` will return true.
+    pub fn advance(&mut self, count: usize) -> Result<(), AddError> {
+        let remaining = self.remaining_capacity();
+        if count > remaining {
+            self.record_overflow(count - remaining);
+            self.track_demand(self.len().saturating_add(count));
+            return Err(AddError::Overflow);
+        }
+        self.grow(count);
+        Ok(())
+    }
+
+    /// Adds up to `count` bytes, written through `ib.spare_capacity_mut()`,
+    /// to the buffer.
+    ///
+    /// Unlike `ib.advance()` the count is silently limited to the
+    /// remaining capacity and the overflow flag is never set. Returns
+    /// the number of bytes added.
+    pub fn advance_clamped(&mut self, count: usize) -> usize {
+        let count = count.min(self.remaining_capacity());
+        self.grow(count);
+        count
+    }
+
+    /// Returns the unused part of the backing buffer as a tokio `ReadBuf`.
+    ///
+    /// After an `AsyncRead` has filled it, add the filled bytes to the
+    /// buffer with `ib.commit_read_buf()`.
+    #[cfg(feature = "tokio")]
+    pub fn read_buf(&mut self) -> tokio::io::ReadBuf<'_> {
+        tokio::io::ReadBuf::new(self.write_area())
+    }
+
+    /// Adds `count` bytes, filled through `ib.read_buf()`, to the buffer.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// Same as `ib.advance()`.
+    #[cfg(feature = "tokio")]
+    pub fn commit_read_buf(&mut self, count: usize) -> Result<(), AddError> {
+        self.advance(count)
+    }
+
+    /// Sets len to `n`, treating the first `n` bytes of the backing
+    /// buffer as buffered data.
+    ///
+    /// Use this after the backing buffer was written externally, for
+    /// instance by DMA into `ib.spare_capacity_mut()`. Nothing is copied
+    /// and the bytes are not checked. If `n` is greater than capacity
+    /// then the value of capacity is used instead.
+    pub fn assume_filled(&mut self, n: usize) {
+        let n = n.min(self.capacity());
+        if n > self.len() {
+            self.grow(n - self.len());
+        }
+        else {
+            self.resize(n);
+        }
+    }
+
+    /// Returns true if the buffered data is stored as one contiguous run
+    /// in the backing buffer.
+    ///
+    /// The buffered data of an InputBuffer always starts at the start of
+    /// the backing buffer, so this always returns true.
+    pub fn is_contiguous(&self) -> bool {
+        true
+    }
+
+    /// Makes the buffered data contiguous if its largest contiguous
+    /// run is shorter than `_contiguous_threshold`.
+    ///
+    /// Returns true if data was moved. The buffered data of an
+    /// InputBuffer is always stored contiguously at the start of the
+    /// backing buffer, so this never moves anything and returns false.
+    pub fn compact_if_below(&mut self, _contiguous_threshold: usize) -> bool {
+        false
+    }
+
+    /// Returns the buffered data and the unused part of the backing
+    /// buffer as two mutable slices.
+    ///
+    /// Data written to the second slice is added to the buffer with
+    /// `ib.advance()`.
+    pub fn split_mut(&mut self) -> (&mut [u8], &mut [u8]) {
+        self.buffer.split_at_mut(self.next_input_pos)
+    }
+
+    /// Splits the buffer into the buffered data and a writer for the
+    /// unused part of the backing buffer.
+    ///
+    /// Data added through the TailWriter is added to the buffer right away
+    /// but is not part of the returned slice.
+    pub fn split(&mut self) -> (&[u8], TailWriter<'_>) {
+        let (front, spare) = self.buffer.split_at_mut(self.next_input_pos);
+        (front, TailWriter {
+            spare,
+            written: 0,
+            len: &mut self.next_input_pos,
+            peak_demand: &mut self.peak_demand,
+            high_watermark: self.high_watermark,
+            high_watermark_armed: &mut self.high_watermark_armed,
+        })
+    }
+
+    /// Returns true if `count` bytes can be written contiguously at the
+    /// back of the buffer.
+    ///
+    /// The unused part of an InputBuffer is always contiguous, so no data
+    /// has to be moved and this is the same as checking `count` against
+    /// the remaining capacity.
+    pub fn ensure_contiguous_write(&mut self, count: usize) -> bool {
+        self.remaining_capacity() >= count
+    }
+
+    /// Returns the first buffered byte, or `None` if the buffer is empty.
+    pub fn first(&self) -> Option<u8> {
+        self.as_slice().first().copied()
+    }
+
+    /// Returns the last buffered byte, or `None` if the buffer is empty.
+    pub fn last(&self) -> Option<u8> {
+        self.as_slice().last().copied()
+    }
+
+    fn peek_array<const K: usize>(&self) -> Option<[u8; K]> {
+        self.as_slice().get(..K).map(|bytes| bytes.try_into().unwrap())
+    }
+
+    /// Returns the first buffered byte without consuming it.
+    pub fn peek_u8(&self) -> Option<u8> {
+        self.first()
+    }
+
+    /// Returns the first two buffered bytes, as a little endian `u16`,
+    /// without consuming them.
+    pub fn peek_u16_le(&self) -> Option<u16> {
+        self.peek_array().map(u16::from_le_bytes)
+    }
+
+    /// Returns the first two buffered bytes, as a big endian `u16`,
+    /// without consuming them.
+    pub fn peek_u16_be(&self) -> Option<u16> {
+        self.peek_array().map(u16::from_be_bytes)
+    }
+
+    /// Returns the first four buffered bytes, as a little endian `u32`,
+    /// without consuming them.
+    pub fn peek_u32_le(&self) -> Option<u32> {
+        self.peek_array().map(u32::from_le_bytes)
+    }
+
+    /// Returns the first four buffered bytes, as a big endian `u32`,
+    /// without consuming them.
+    pub fn peek_u32_be(&self) -> Option<u32> {
+        self.peek_array().map(u32::from_be_bytes)
+    }
+
+    /// Copies the last buffered bytes into `output` without consuming them.
+    ///
+    /// Copies the last `min(len, output.len())` bytes, in order, to the
+    /// start of `output` and returns the number of bytes copied.
+    pub fn peek_tail(&self, output: &mut [u8]) -> usize {
+        let count = self.len().min(output.len());
+        output[..count].copy_from_slice(&self.as_slice()[self.len() - count..]);
+        count
+    }
+
+    /// Copies all buffered data into `output` without consuming it.
+    ///
+    /// Returns `Ok(len)` on success. If `output` is too small
+    /// nothing is copied and `Err(len)` is returned.
+    pub fn snapshot(&self, output: &mut [u8]) -> Result<usize, usize> {
+        let len = self.len();
+        if output.len() < len {
+            return Err(len);
+        }
+        output[..len].copy_from_slice(self.as_slice());
+        Ok(len)
+    }
+
+    /// Returns a ReadCursor over the buffered data.
+    ///
+    /// Reading through the cursor does not consume any data,
+    /// use `ib.consume()` with `cursor.position()` bytes to commit
+    /// what has been parsed.
+    pub fn cursor(&self) -> ReadCursor<'_> {
+        ReadCursor::new(self.as_slice())
+    }
+
+    /// Returns a BitReader over the buffered data.
+    ///
+    /// Reading through the bit reader does not consume any data,
+    /// use `ib.consume()` with `reader.bytes_read()` bytes to commit
+    /// what has been parsed.
+    pub fn bit_reader(&self) -> BitReader<'_> {
+        BitReader::new(self.as_slice())
+    }
+
+    /// Returns the largest len the buffer has reached, or would have
+    /// reached if no data had been dropped because of overflows.
+    ///
+    /// Use `ib.clear()` to reset it.
+    pub fn peak_demand(&self) -> usize {
+        self.peak_demand
+    }
+
+    /// Returns a capacity that would have avoided all overflows so far.
+    ///
+    /// This is `ib.peak_demand()` rounded up to a power of two.
+    pub fn suggested_capacity(&self) -> usize {
+        self.peak_demand.checked_next_power_of_two().unwrap_or(usize::MAX)
+    }
+
+    /// Records the current fill level in the occupancy histogram.
+    ///
+    /// The fill level, `ib.backlog_ratio_permille()`, is counted in one
+    /// of 8 equally sized buckets, with a full buffer counted in the
+    /// last bucket.
+    pub fn sample_occupancy(&mut self) {
+        let bucket = (self.backlog_ratio_permille() as usize * 8 / 1000).min(7);
+        self.occupancy[bucket] = self.occupancy[bucket].saturating_add(1);
+    }
+
+    /// Returns the number of samples counted in each occupancy bucket.
+    ///
+    /// The histogram is not reset by `ib.clear()`, since it typically
+    /// spans many fill and drain cycles, use `ib.reset_occupancy()`.
+    pub fn occupancy_buckets(&self) -> [u32; 8] {
+        self.occupancy
+    }
+
+    /// Resets the occupancy histogram.
+    pub fn reset_occupancy(&mut self) {
+        self.occupancy = [0; 8];
+    }
+
+    /// Sets a callback invoked when len reaches `threshold`.
+    ///
+    /// The callback is invoked once each time len goes from below
+    /// `threshold` to `threshold` or above, it is invoked again only
+    /// after len has dropped below `threshold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` the len at which to invoke the callback.
+    /// * `callback` the function to invoke.
+    pub fn set_high_watermark(&mut self, threshold: usize, callback: fn()) {
+        self.high_watermark = Some((threshold, callback));
+        self.high_watermark_armed = self.len() < threshold;
+    }
+
+    /// Returns true if an overflow has been detected
+    ///
+    /// Use `ib.clear()` to clear the overflow flag.
+    pub fn overflown(&self) -> bool {
+        self.overflow
+    }
+
+    /// Returns the number of times an overflow has occurred.
+    ///
+    /// Every failing `push` and every truncated `push_multiple`
+    /// counts as one overflow. Use `ib.clear()` to reset the count.
+    pub fn overflow_count(&self) -> usize {
+        self.overflow_count
+    }
+
+    /// Returns the total number of bytes that could not be
+    /// added because of overflows.
+    ///
+    /// Use `ib.clear()` to reset the count.
+    pub fn bytes_dropped(&self) -> usize {
+        self.bytes_dropped
+    }
+
+    /// Sets the sequence number recorded by later overflows.
+    ///
+    /// There is no clock in `no_std`, so the caller feeds a tick or
+    /// event counter of its own, typically before pushing.
+    pub fn mark_overflow_seq(&mut self, seq: u64) {
+        self.overflow_seq = seq;
+    }
+
+    /// Returns the sequence number, set with `ib.mark_overflow_seq()`,
+    /// at the most recent overflow, or `None` if nothing overflowed
+    /// since the buffer was created or cleared.
+    pub fn last_overflow_seq(&self) -> Option<u64> {
+        self.last_overflow_seq
+    }
+
+    /// Resizes the buffer.
+    ///
+    /// This does *not* clear the overflow flag,
+    /// use `ib.clear()` for that.
+    ///
+    /// If `new_size` is greater than capacity then
+    /// the value of capacity is used instead.
+    ///
+    /// Returns the resulting len, which is less than `new_size`
+    /// if it was clamped.
+    pub fn resize(&mut self, new_size: usize) -> usize {
+        self.next_input_pos = new_size.min(self.capacity());
+        debug_assert!(self.next_input_pos <= self.capacity());
+        self.track_demand(self.next_input_pos);
+        self.update_high_watermark();
+        self.next_input_pos
+    }
+
+    /// Resizes the buffer like `ib.resize()` and returns
+    /// the change in len.
+    ///
+    /// The result is positive when the buffer grew and negative
+    /// when it shrunk.
+    pub fn resize_returning_delta(&mut self, new_size: usize) -> isize {
+        let old_len = self.len();
+        self.resize(new_size);
+        self.len() as isize - old_len as isize
+    }
+
+    /// Keeps only the last `count` bytes of buffered data.
+    ///
+    /// Data before the last `count` bytes is dropped and the remaining
+    /// data is moved to the start of the buffer. Nothing happens if
+    /// len is `count` or less.
+    pub fn keep_last(&mut self, count: usize) {
+        if self.len() > count {
+            self.discard_front(self.len() - count);
+            self.update_high_watermark();
+        }
+    }
+
+    /// Removes leading and trailing ASCII whitespace from the
+    /// buffered data.
+    ///
+    /// Leading whitespace is dropped, moving the remaining data to the
+    /// start of the buffer, and len is reduced to drop trailing
+    /// whitespace.
+    pub fn trim(&mut self) {
+        let leading = self.position(|b| !b.is_ascii_whitespace()).unwrap_or(self.len());
+        self.discard_front(leading);
+        let trimmed_len = self.as_slice()
+            .iter()
+            .rposition(|b| !b.is_ascii_whitespace())
+            .map_or(0, |last| last + 1);
+        self.resize(trimmed_len);
+    }
+
+    /// Clears the buffer.
+    ///
+    /// This clears the overflow flag, resets the overflow
+    /// statistics, the last overflow sequence number and peak
+    /// demand, forgets the rewind history and sets len to 0.
+    pub fn clear(&mut self) {
+        self.next_input_pos = 0;
+        self.history_len = 0;
+        self.update_high_watermark();
+        self.overflow = false;
+        self.overflow_count = 0;
+        self.bytes_dropped = 0;
+        self.last_overflow_seq = None;
+        self.peak_demand = 0;
+    }
+
+    /// Clears the buffer, then sets len to `new_len` with every
+    /// byte set to `value`.
+    ///
+    /// If `new_len` is greater than capacity then
+    /// the value of capacity is used instead.
+    pub fn clear_and_fill(&mut self, new_len: usize, value: u8) {
+        self.clear();
+        self.resize(new_len);
+        self.fill(value);
+    }
+
+    /// Clears the buffer, then adds all of `values`.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// If `values` doesn't fit nothing is added, the buffer is left
+    /// empty, an error is returned and `x.overflown()` will return true.
+    pub fn set_contents(&mut self, values: &[u8]) -> Result<(), AddError> {
+        self.clear();
+        self.push_all(values)
+    }
+
+    /// Clears the buffer and overwrites the backing buffer with zeros.
+    ///
+    /// Consuming data leaves stale copies past len, so the entire
+    /// backing buffer, including any rewind history, is wiped, not
+    /// only the buffered data. The writes
+    /// are volatile so they are not optimized away.
+    pub fn clear_zeroed(&mut self) {
+        for byte in self.history.iter_mut().chain(self.buffer.iter_mut()) {
+            // SAFETY: `byte` is a valid, aligned and exclusive reference.
+            unsafe { core::ptr::write_volatile(byte, 0) };
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        self.clear();
+    }
+
+    /// Returns the index of the first occurrence of `needle`
+    /// in the buffered data.
+    pub fn find(&self, needle: u8) -> Option<usize> {
+        self.position(|b| b == needle)
+    }
+
+    /// Returns the index of the first occurrence of `pattern`
+    /// in the buffered data.
+    ///
+    /// An empty `pattern` is found at index 0.
+    pub fn find_subslice(&self, pattern: &[u8]) -> Option<usize> {
+        if pattern.is_empty() {
+            return Some(0);
+        }
+        self.as_slice().windows(pattern.len()).position(|w| w == pattern)
+    }
+
+    /// Returns the index of the first byte in the buffered data
+    /// for which `f` returns true.
+    pub fn position<F: FnMut(u8) -> bool>(&self, mut f: F) -> Option<usize> {
+        self.as_slice().iter().position(|b| f(*b))
+    }
+
+    /// Returns the index of the last occurrence of `needle`
+    /// in the buffered data.
+    pub fn rfind(&self, needle: u8) -> Option<usize> {
+        self.as_slice().iter().rposition(|b| *b == needle)
+    }
+
+    /// Returns the number of bytes before the first `delimiter`,
+    /// or `None` if `delimiter` isn't buffered.
+    pub fn bytes_until(&self, delimiter: u8) -> Option<usize> {
+        self.find(delimiter)
+    }
+
+    /// Returns true if `needle` is part of the buffered data.
+    pub fn contains(&self, needle: u8) -> bool {
+        self.find(needle).is_some()
+    }
+
+    /// Returns true if the buffered data starts with `prefix`.
+    pub fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.as_slice().starts_with(prefix)
+    }
+
+    /// Returns true if the buffered data ends with `suffix`.
+    pub fn ends_with(&self, suffix: &[u8]) -> bool {
+        self.as_slice().ends_with(suffix)
+    }
+
+    /// Returns true if the buffered data equals `other`, comparing
+    /// in constant time.
+    ///
+    /// Every byte is compared regardless of where the first difference
+    /// is, so the time taken doesn't reveal it. Lengths are not secret,
+    /// different lengths return false immediately.
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let diff = self.as_slice()
+            .iter()
+            .zip(other)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        core::hint::black_box(diff) == 0
+    }
+
+    /// Folds the buffered data into a single value without consuming it.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` the initial value.
+    /// * `f` combines the value so far with the next byte.
+    pub fn fold_bytes<B, F: FnMut(B, u8) -> B>(&self, init: B, mut f: F) -> B {
+        self.as_slice().iter().fold(init, |acc, b| f(acc, *b))
+    }
+
+    /// Adds the number of occurrences of each byte value in the
+    /// buffered data to `output`, indexed by byte value.
+    ///
+    /// `output` is not reset first, so counts can be accumulated
+    /// over several calls.
+    pub fn byte_histogram(&self, output: &mut [u32; 256]) {
+        for byte in self.as_slice() {
+            output[*byte as usize] = output[*byte as usize].saturating_add(1);
+        }
+    }
+
+    /// Returns a copy of `range` of the buffered data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is not within the buffered data.
+    #[cfg(feature = "alloc")]
+    pub fn freeze_range(&self, range: core::ops::Range<usize>) -> Box<[u8]> {
+        self.as_slice()[range].into()
+    }
+
+    /// Returns a hex dump of the buffered data.
+    ///
+    /// Each line holds the offset, up to 16 bytes in hex and
+    /// their printable ASCII characters, with `.` for other bytes.
+    #[cfg(feature = "alloc")]
+    pub fn hex_dump(&self) -> String {
+        use core::fmt::Write;
+
+        let mut dump = String::new();
+        for (line, chunk) in self.as_slice().chunks(16).enumerate() {
+            let _ = write!(dump, "{:08x}:", line * 16);
+            for byte in chunk {
+                let _ = write!(dump, " {:02x}", byte);
+            }
+            for _ in chunk.len()..16 {
+                dump.push_str("   ");
+            }
+            dump.push_str("  ");
+            for byte in chunk {
+                dump.push(if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' });
+            }
+            dump.push('\n');
+        }
+        dump
+    }
+
+    /// Returns an iterator over `K`-byte arrays of the buffered data.
+    ///
+    /// Any trailing data shorter than `K` is not yielded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` is 0.
+    pub fn array_chunks<const K: usize>(&self) -> impl Iterator<Item = &[u8; K]> {
+        self.as_slice()
+            .chunks_exact(K)
+            .map(|chunk| chunk.try_into().unwrap())
+    }
+
+    /// Returns an iterator over the complete lines in the buffered data.
+    ///
+    /// Lines are separated by `\n`, which is not included in the yielded
+    /// slices. Data after the last `\n` is an incomplete line and is
+    /// not yielded. No data is consumed, each yielded line accounts for
+    /// its own length plus one byte for the newline.
+    pub fn lines(&self) -> impl Iterator<Item = &[u8]> {
+        let data = self.as_slice();
+        data.iter()
+            .rposition(|b| *b == b'\n')
+            .map(|last_newline| &data[..last_newline])
+            .into_iter()
+            .flat_map(|complete| complete.split(|b| *b == b'\n'))
+    }
+
+    /// Finds the complete frames in the buffered data without consuming.
+    ///
+    /// A frame ends with `delim`, the index of each frame's `delim` is
+    /// written to `output` in order. Returns the number of frames found,
+    /// at most `output.len()`. Consuming through the last frame found
+    /// means consuming `output[count - 1] + 1` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `delim` the byte ending each frame.
+    /// * `output` receives the frame end offsets.
+    pub fn frame_offsets(&self, delim: u8, output: &mut [usize]) -> usize {
+        let ends = self.as_slice()
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == delim)
+            .map(|(i, _)| i);
+        let mut count = 0;
+        for (slot, end) in output.iter_mut().zip(ends) {
+            *slot = end;
+            count += 1;
+        }
+        count
+    }
+
+    /// Replaces every occurrence of `old` in the buffered data with `new`.
+    ///
+    /// Returns the number of bytes replaced, len is not changed.
+    pub fn replace_all(&mut self, old: u8, new: u8) -> usize {
+        let mut replaced = 0;
+        for byte in self.buffer[..self.next_input_pos].iter_mut().filter(|b| **b == old) {
+            *byte = new;
+            replaced += 1;
+        }
+        replaced
+    }
+
+    /// Takes an unsigned LEB128 varint from the start of the buffer.
+    ///
+    /// Returns `Ok(None)`, without consuming anything, if the buffer
+    /// doesn't start with a complete varint yet. Returns
+    /// `Err(VarintError::Overflow)`, also without consuming anything,
+    /// if the varint can't fit in a `u64`, no matter what data follows.
+    pub fn read_varint(&mut self) -> Result<Option<u64>, VarintError> {
+        let mut value = 0u64;
+        for (index, byte) in self.as_slice().iter().enumerate() {
+            if index == 9 && *byte > 1 {
+                return Err(VarintError::Overflow);
+            }
+            value |= ((byte & 0x7f) as u64) << (7 * index);
+            if byte & 0x80 == 0 {
+                self.remove_front(index + 1);
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Overwrites buffered data starting at `offset` with `values`.
+    ///
+    /// len is not changed. If `values` would extend past len nothing
+    /// is written and an error is returned, the overflow flag is not set.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` the index of the first byte to overwrite.
+    /// * `values` the data to write.
+    pub fn write_at(&mut self, offset: usize, values: &[u8]) -> Result<(), AddError> {
+        if offset > self.len() || values.len() > self.len() - offset {
+            return Err(AddError::Overflow);
+        }
+        self.buffer[offset..offset + values.len()].copy_from_slice(values);
+        Ok(())
+    }
+
+    /// Swaps two bytes of the buffered data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is not less than len.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.buffer[..self.next_input_pos].swap(a, b);
+    }
+
+    /// Sets every byte of the buffered data to `value`, len is not changed.
+    pub fn fill(&mut self, value: u8) {
+        self.buffer[..self.next_input_pos].fill(value);
+    }
+
+    /// XORs the buffered data with the repeating `key`, len is not changed.
+    ///
+    /// Applying the same key twice restores the original data.
+    /// An empty `key` leaves the data unchanged.
+    pub fn xor_mask(&mut self, key: &[u8]) {
+        if key.is_empty() {
+            return;
+        }
+        for (byte, k) in self.buffer[..self.next_input_pos].iter_mut().zip(key.iter().cycle()) {
+            *byte ^= k;
+        }
+    }
+
+    /// Reverses the order of the buffered data, len is not changed.
+    pub fn reverse(&mut self) {
+        self.buffer[..self.next_input_pos].reverse();
+    }
+
+    /// Returns an iterator that consumes the buffered data one byte
+    /// at a time.
+    ///
+    /// Only the bytes yielded by the iterator are consumed, any
+    /// remaining data is moved to the start of the buffer when the
+    /// iterator is dropped.
+    pub fn drain(&mut self) -> Drain<'_, 'a> {
+        Drain {
+            buffer: self,
+            taken: 0,
+        }
+    }
+
+    /// Moves the buffer to a new backing buffer.
+    ///
+    /// The buffered data is copied to `new_buffer` and all other state,
+    /// like the overflow flag and policy, is kept. The rewind history
+    /// stays in the old backing buffer and is not kept. If `new_buffer` is
+    /// too small for the buffered data the original buffer is returned
+    /// unchanged as the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_buffer` the new backing buffer, typically a larger one.
+    #[allow(clippy::result_large_err)]
+    pub fn rebase_into<'b>(self, new_buffer: &'b mut [u8]) -> Result<InputBuffer<'b>, InputBuffer<'a>> {
+        if new_buffer.len() < self.len() {
+            return Err(self);
+        }
+        new_buffer[..self.len()].copy_from_slice(self.as_slice());
+        Ok(InputBuffer {
+            buffer: new_buffer,
+            next_input_pos: self.next_input_pos,
+            overflow: self.overflow,
+            overflow_count: self.overflow_count,
+            bytes_dropped: self.bytes_dropped,
+            policy: self.policy,
+            high_watermark: self.high_watermark,
+            high_watermark_armed: self.high_watermark_armed,
+            min_fill: self.min_fill,
+            peak_demand: self.peak_demand,
+            occupancy: self.occupancy,
+            history: &mut [],
+            history_len: 0,
+            overflow_seq: self.overflow_seq,
+            last_overflow_seq: self.last_overflow_seq,
+        })
+    }
+
+    /// Returns a `Stream` yielding the buffered data in chunks.
+    ///
+    /// See `ByteStream` for details.
+    #[cfg(feature = "futures")]
+    pub fn stream(&mut self) -> crate::stream::ByteStream<'_, 'a> {
+        crate::stream::ByteStream::new(self)
+    }
+
+    /// Returns the backing buffer and len.
+    ///
+    /// The first len bytes of the backing buffer are the buffered data.
+    /// For a buffer created with `InputBuffer::new_with_rewind()` the
+    /// rewind history is not part of the returned backing buffer.
+    pub fn into_parts(self) -> (&'a mut [u8], usize) {
+        (self.buffer, self.next_input_pos)
+    }
+
+    /// Turns the buffer into an iterator over the buffered data.
+    pub fn into_drain(self) -> impl Iterator<Item = u8> + 'a {
+        let len = self.next_input_pos;
+        let buffer: &'a [u8] = self.buffer;
+        buffer[..len].iter().copied()
+    }
+
+    /// Takes data from the start of the buffer, moving any
+    /// remaining data to the start of the buffer and decreases
+    /// len.
+    ///
+    /// This is the only way to retrieve data from the buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` The output buffer for data.
+    ///
+    /// # Overflow behaviour
+    ///
+    /// Consuming never sets or clears the overflow flag, not even when
+    /// `output` is larger than len. Use `ib.clear()` to clear it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate uio_buffer;
+    /// use uio_buffer::input_buffer;
+    /// let mut backing_buffer = [0u8; 10];
+    /// let mut buffer = input_buffer::InputBuffer::new(&mut backing_buffer);
+    /// buffer.push_multiple(&[1,2,3,4,5]);
+    /// assert_eq!(buffer.len(), 5);
+    /// let mut read_buffer = [0u8; 3];
+    /// let consumed = buffer.consume(&mut read_buffer);
+    /// assert_eq!(consumed, 3);
+    /// assert_eq!(read_buffer, [1,2,3]);
+    /// assert_eq!(buffer.len(), 2);
+    /// // buffer contains [4,5]
+    /// ```
+    pub fn consume(&mut self, output: &mut [u8]) -> usize {
+        let bytes_to_consume = self.len().min(output.len());
+        if bytes_to_consume == 0 {
+            return 0;
+        }
+
+        debug_assert!(bytes_to_consume <= self.next_input_pos);
+        let split_input = self.buffer.split_at(bytes_to_consume);
+        let output_area = output.split_at_mut(bytes_to_consume).0;
+        let input_area = split_input.0;
+        output_area.copy_from_slice(input_area);
+
+        self.remove_front(bytes_to_consume);
+        bytes_to_consume
+    }
+
+    /// Parses the start of the buffer through a ReadCursor and consumes
+    /// exactly the bytes read by the cursor.
+    ///
+    /// If `parse` returns `None` nothing is consumed. A cursor borrows
+    /// the buffer, so parsing and committing happen in one call
+    /// instead of passing the cursor back in.
+    ///
+    /// # Arguments
+    ///
+    /// * `parse` reads from the cursor, returning `None` to consume nothing.
+    pub fn consume_cursor<T, F>(&mut self, parse: F) -> Option<T>
+    where
+        F: FnOnce(&mut ReadCursor) -> Option<T>,
+    {
+        let mut cursor = self.cursor();
+        let value = parse(&mut cursor)?;
+        let position = cursor.position();
+        self.remove_front(position);
+        Some(value)
+    }
+
+    /// Lets `f` inspect the buffered data and decide how many bytes
+    /// to take from the start of the buffer.
+    ///
+    /// If `f` returns `Some(count)` up to `count` bytes are consumed into
+    /// `output`, limited like `ib.consume_up_to()`, and the number of
+    /// bytes consumed is returned. If `f` returns `None` nothing is
+    /// consumed and `None` is returned.
+    pub fn consume_if<F: FnOnce(&[u8]) -> Option<usize>>(&mut self, f: F, output: &mut [u8]) -> Option<usize> {
+        let count = f(self.as_slice())?;
+        Some(self.consume_up_to(count, output))
+    }
+
+    /// Takes up to `max` bytes from the start of the buffer.
+    ///
+    /// Works like `ib.consume()` but consumes at most `max` bytes.
+    /// Returns the number of bytes consumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` the maximum number of bytes to take.
+    /// * `output` The output buffer for data.
+    pub fn consume_up_to(&mut self, max: usize, output: &mut [u8]) -> usize {
+        let count = max.min(output.len());
+        self.consume(&mut output[..count])
+    }
+
+    /// Takes exactly `at` bytes from the start of the buffer.
+    ///
+    /// Returns `Ok(at)` on success. If fewer than `at` bytes are buffered,
+    /// or `output` can't hold `at` bytes, nothing is consumed and
+    /// `Err(len)` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` the number of bytes to take.
+    /// * `output` The output buffer for data.
+    pub fn split_to(&mut self, at: usize, output: &mut [u8]) -> Result<usize, usize> {
+        if at > self.len() || at > output.len() {
+            return Err(self.len());
+        }
+        Ok(self.consume(&mut output[..at]))
+    }
+
+    /// Takes exactly `K` bytes from the start of the buffer as an array.
+    ///
+    /// Returns `None`, without consuming anything, if fewer than
+    /// `K` bytes are buffered. This makes it suitable for fixed size
+    /// headers, which are only taken once complete.
+    pub fn consume_array<const K: usize>(&mut self) -> Option<[u8; K]> {
+        let mut output = [0u8; K];
+        if self.read_exact(&mut output) {
+            Some(output)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Takes the leading run of bytes matching `f` from the start
+    /// of the buffer.
+    ///
+    /// Stops at the first byte not matching `f` or when `output` is full.
+    /// Returns the number of bytes consumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` returns true for bytes that should be consumed.
+    /// * `output` The output buffer for data.
+    pub fn consume_matching<F: FnMut(u8) -> bool>(&mut self, mut f: F, output: &mut [u8]) -> usize {
+        let run = self.as_slice()
+            .iter()
+            .take(output.len())
+            .take_while(|b| f(**b))
+            .count();
+        self.consume(&mut output[..run])
+    }
+
+    /// Takes up to `max` bytes from the start of the buffer
+    /// and returns them in a `Vec`.
+    #[cfg(feature = "alloc")]
+    pub fn consume_to_vec(&mut self, max: usize) -> Vec<u8> {
+        let count = max.min(self.len());
+        let output = self.as_slice()[..count].to_vec();
+        self.remove_front(count);
+        output
+    }
+
+    /// Takes a `\n` terminated line from the start of the buffer
+    /// and returns it, without `\n`, as a `String`.
+    ///
+    /// Invalid UTF-8 is replaced like `String::from_utf8_lossy()` does.
+    /// Returns `None`, without consuming anything, if no complete line
+    /// is buffered.
+    #[cfg(feature = "alloc")]
+    pub fn consume_line_lossy(&mut self) -> Option<String> {
+        let line_len = self.find(b'\n')?;
+        let line = String::from_utf8_lossy(&self.as_slice()[..line_len]).into_owned();
+        self.remove_front(line_len + 1);
+        Some(line)
+    }
+
+    /// Writes exactly `count` bytes from the start of the buffer to `writer`.
+    ///
+    /// The bytes are consumed only if all of them were written, in
+    /// which case `Ok(true)` is returned. If fewer than `count` bytes
+    /// are buffered nothing is written and `Ok(false)` is returned.
+    #[cfg(feature = "std")]
+    pub fn drain_exact_to_writer<W: std::io::Write>(&mut self, count: usize, writer: &mut W) -> std::io::Result<bool> {
+        if count > self.len() {
+            return Ok(false);
+        }
+        writer.write_all(&self.as_slice()[..count])?;
+        self.remove_front(count);
+        Ok(true)
+    }
+
+    /// Takes everything up to and including the first occurrence of
+    /// `pattern` from the start of the buffer.
+    ///
+    /// As much of the consumed data as fits is copied to `output`, the
+    /// rest is discarded. Returns the number of bytes consumed, or `None`,
+    /// without consuming anything, if `pattern` isn't buffered.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` the delimiter to consume through.
+    /// * `output` The output buffer for data.
+    pub fn consume_through(&mut self, pattern: &[u8], output: &mut [u8]) -> Option<usize> {
+        let count = self.find_subslice(pattern)? + pattern.len();
+        let copied = count.min(output.len());
+        output[..copied].copy_from_slice(&self.as_slice()[..copied]);
+        self.remove_front(count);
+        Some(count)
+    }
+
+    /// Takes a `\r\n` terminated line from the start of the buffer.
+    ///
+    /// As much of the line, without `\r\n`, as fits is copied to
+    /// `output` and the whole line including `\r\n` is consumed.
+    /// Returns the length of the line, which is greater than
+    /// `output.len()` if the line was truncated.
+    ///
+    /// Returns `None`, without consuming anything, if no complete line
+    /// is buffered. A `\n` without a preceding `\r` doesn't end a line.
+    pub fn read_line_crlf(&mut self, output: &mut [u8]) -> Option<usize> {
+        let line_len = self.as_slice().windows(2).position(|w| w == b"\r\n")?;
+        let copied = line_len.min(output.len());
+        output[..copied].copy_from_slice(&self.as_slice()[..copied]);
+        self.remove_front(line_len + 2);
+        Some(line_len)
+    }
+
+    /// Takes data from the start of the buffer like `ib.consume()`,
+    /// but returns `None` if the buffer is empty.
+    pub fn try_consume(&mut self, output: &mut [u8]) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.consume(output))
+    }
+
+    /// Takes data from the start of the buffer into several output
+    /// buffers, filling them in order.
+    ///
+    /// Remaining data is moved to the start of the buffer once, after
+    /// all output buffers have been filled. Returns the total number of
+    /// bytes consumed.
+    pub fn consume_into_all(&mut self, outputs: &mut [&mut [u8]]) -> usize {
+        let mut consumed = 0;
+        for output in outputs.iter_mut() {
+            let count = output.len().min(self.len() - consumed);
+            output[..count].copy_from_slice(&self.as_slice()[consumed..consumed + count]);
+            consumed += count;
+        }
+        self.remove_front(consumed);
+        consumed
+    }
+
+    /// Puts up to `count` of the most recently consumed bytes back at
+    /// the start of the buffer.
+    ///
+    /// Only buffers created with `InputBuffer::new_with_rewind()` keep
+    /// consumed data. Dropping data without consuming it, through
+    /// `OverflowPolicy::DropOldest`, `ib.keep_last()` or `ib.trim()`,
+    /// forgets all kept data. The number of bytes restored is limited
+    /// by the kept data and the remaining capacity, and is returned.
+    pub fn rewind(&mut self, count: usize) -> usize {
+        let count = count.min(self.history_len).min(self.remaining_capacity());
+        let len = self.len();
+        self.buffer.copy_within(0..len, count);
+        self.buffer[..count].copy_from_slice(&self.history[self.history_len - count..self.history_len]);
+        self.history_len -= count;
+        self.grow(count);
+        count
+    }
+
+    /// Takes exactly `output.len()` bytes from the start of the buffer.
+    ///
+    /// If fewer bytes than `output.len()` are available nothing is
+    /// consumed and false is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` The output buffer for data, filled completely on success.
+    pub fn read_exact(&mut self, output: &mut [u8]) -> bool {
+        if self.len() < output.len() {
+            return false;
+        }
+        self.consume(output);
+        true
+    }
+}
+
+/// Buffers are equal when their buffered data is equal,
+/// regardless of capacity and overflow state.
+impl<'b> PartialEq<InputBuffer<'b>> for InputBuffer<'_> {
+    fn eq(&self, other: &InputBuffer<'b>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for InputBuffer<'_> {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for InputBuffer<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "InputBuffer {{ len: {=usize}, capacity: {=usize}, overflown: {=bool}, data: {=[u8]:x} }}",
+            self.len(),
+            self.capacity(),
+            self.overflown(),
+            self.as_slice()
+        )
+    }
+}
+
+/// Calls the high watermark callback when `len` reaches its threshold,
+/// once per crossing. Falling below the threshold re-arms it.
+fn check_high_watermark(len: usize, high_watermark: Option<(usize, fn())>, armed: &mut bool) {
+    if let Some((threshold, callback)) = high_watermark {
+        if len < threshold {
+            *armed = true;
+        }
+        else if *armed {
+            *armed = false;
+            callback();
+        }
+    }
+}
+
+/// Writer returned by `InputBuffer::split()`.
+///
+/// The TailWriter doesn't update the overflow state of the InputBuffer,
+/// overflows are only reported through the results of its methods.
+pub struct TailWriter<'b> {
+    spare: &'b mut [u8],
+    written: usize,
+    len: &'b mut usize,
+    peak_demand: &'b mut usize,
+    high_watermark: Option<(usize, fn())>,
+    high_watermark_armed: &'b mut bool,
+}
+
+impl TailWriter<'_> {
+    /// Adds `count` bytes, already written to `spare`, to the buffer.
+    fn grow(&mut self, count: usize) {
+        self.written += count;
+        *self.len += count;
+        *self.peak_demand = (*self.peak_demand).max(*self.len);
+        check_high_watermark(*self.len, self.high_watermark, self.high_watermark_armed);
+    }
+
+    /// Push data to the back of the buffer.
+    ///
+    /// Returns an error if the buffer is full.
+    pub fn push(&mut self, value: u8) -> Result<(), AddError> {
+        if self.remaining_capacity() == 0 {
+            return Err(AddError::Overflow);
+        }
+        self.spare[self.written] = value;
+        self.grow(1);
+        Ok(())
+    }
+
+    /// Pushes as much of `values` as fits to the back of the buffer.
+    ///
+    /// Returns the number of bytes added.
+    pub fn push_multiple(&mut self, values: &[u8]) -> usize {
+        let count = values.len().min(self.remaining_capacity());
+        self.spare[self.written..self.written + count].copy_from_slice(&values[..count]);
+        self.grow(count);
+        count
+    }
+
+    /// Returns the number of bytes that can still be added.
+    pub fn remaining_capacity(&self) -> usize {
+        self.spare.len() - self.written
+    }
+}
+
+/// Iterator returned by `InputBuffer::drain()`.
+pub struct Drain<'b, 'a> {
+    buffer: &'b mut InputBuffer<'a>,
+    taken: usize,
+}
+
+impl Iterator for Drain<'_, '_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let value = *self.buffer.as_slice().get(self.taken)?;
+        self.taken += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.buffer.len() - self.taken;
+        (remaining, Some(remaining))
+    }
+}
+
+impl Drop for Drain<'_, '_> {
+    fn drop(&mut self) {
+        self.buffer.remove_front(self.taken);
+    }
+}
+
+/// Moves data from the start of `src` to the back of `dst`.
+///
+/// Up to `max` bytes are moved, limited by the length of `src` and
+/// the remaining capacity of `dst`. The data is copied once, directly
+/// between the backing buffers.
+///
+/// Returns the number of bytes moved. This never sets the overflow
+/// flag of `dst`.
+pub fn transfer(src: &mut InputBuffer, dst: &mut InputBuffer, max: usize) -> usize {
+    let count = max.min(src.len()).min(dst.remaining_capacity());
+    dst.write_area()[..count].copy_from_slice(&src.buffer[..count]);
+    dst.grow(count);
+    src.remove_front(count);
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transfer, InputBuffer, OverflowPolicy, VarintError};
+
+    #[test]
+    fn capacity_len_on_new() {
+        let mut buffer = [0u8; 10];
+        let target_len = buffer.len();
+
+        let input_buffer = InputBuffer::new(&mut buffer);
+        assert_eq!(input_buffer.capacity(), target_len);
+        assert_eq!(input_buffer.len(), 0);
+    }
+
+    #[test]
+    fn len_increases_on_add() {
+        let mut buffer = [0u8; 10];
+
+        {
+            let mut input_buffer = InputBuffer::new(&mut buffer);
+            input_buffer.push(10).unwrap();
+            assert_eq!(input_buffer.len(), 1);
+
+            input_buffer.push(20).unwrap();
+            assert_eq!(input_buffer.len(), 2);
+        }
+
+        assert_eq!(buffer[0], 10);
+        assert_eq!(buffer[1], 20);
+    }
+
+    #[test]
+    fn detect_overflow()
+    {
+        let mut buffer = [0u8; 2];
+
+        {
+            let mut input_buffer = InputBuffer::new(&mut buffer);
+            input_buffer.push(10).unwrap();
+            input_buffer.push(20).unwrap();
+
+            assert!(!input_buffer.overflown());
+            assert!(input_buffer.push(30).is_err());
+            assert!(input_buffer.overflown());
+
+            input_buffer.clear();
+            assert!(!input_buffer.overflown());
+            assert_eq!(input_buffer.len(), 0);
+        }
+
+        assert_eq!(buffer[0], 10);
+        assert_eq!(buffer[1], 20);
+    }
+
+    #[test]
+    fn consume() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        let mut consume_buffer = [0u8; 2];
+
+        input_buffer.push(1).unwrap();
+        assert_eq!(input_buffer.len(), 1);
+        let consumed = input_buffer.consume(&mut consume_buffer);
+        assert_eq!(consumed, 1);
+        assert_eq!(consume_buffer[0], 1);
+        assert_eq!(input_buffer.len(), 0);
+
+        input_buffer.push(2).unwrap();
         input_buffer.push(3).unwrap();
         let consumed = input_buffer.consume(&mut consume_buffer);
         assert_eq!(consumed, 2);
@@ -243,31 +1897,1657 @@ mod tests {
         input_buffer.push(4).unwrap();
         input_buffer.push(5).unwrap();
         input_buffer.push(6).unwrap();
-        let consumed = input_buffer.consume(&mut consume_buffer);
-        assert_eq!(consumed, 2);
-        assert_eq!(consume_buffer[0], 4);
-        assert_eq!(consume_buffer[1], 5);
+        let consumed = input_buffer.consume(&mut consume_buffer);
+        assert_eq!(consumed, 2);
+        assert_eq!(consume_buffer[0], 4);
+        assert_eq!(consume_buffer[1], 5);
+        assert_eq!(input_buffer.len(), 1);
+
+        let consumed = input_buffer.consume(&mut consume_buffer);
+        assert_eq!(consumed, 1);
+        assert_eq!(consume_buffer[0], 6);
+        assert_eq!(input_buffer.len(), 0);
+
+        input_buffer.push(4).unwrap();
+        input_buffer.push(5).unwrap();
+        input_buffer.push(6).unwrap();
+        let consumed = input_buffer.consume(&mut consume_buffer);
+        assert_eq!(consumed, 2);
+        assert_eq!(consume_buffer[0], 4);
+        assert_eq!(consume_buffer[1], 5);
+        assert_eq!(input_buffer.len(), 1);
+
+        input_buffer.push(7).unwrap();
+        let consumed = input_buffer.consume(&mut consume_buffer);
+        assert_eq!(consumed, 2);
+        assert_eq!(consume_buffer[0], 6);
+        assert_eq!(consume_buffer[1], 7);
+        assert_eq!(input_buffer.len(), 0);
+    }
+
+    #[test]
+    fn read_exact() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let mut record = [0u8; 4];
+
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert!(!input_buffer.read_exact(&mut record));
+        assert_eq!(input_buffer.len(), 3);
+        assert_eq!(record, [0, 0, 0, 0]);
+
+        input_buffer.push(4).unwrap();
+        assert!(input_buffer.read_exact(&mut record));
+        assert_eq!(record, [1, 2, 3, 4]);
+        assert_eq!(input_buffer.len(), 0);
+    }
+
+    #[test]
+    fn overflow_count() {
+        let mut buffer = [0u8; 2];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        input_buffer.push_multiple(&[1, 2]);
+        assert_eq!(input_buffer.overflow_count(), 0);
+
+        assert!(input_buffer.push(3).is_err());
+        assert!(input_buffer.push(4).is_err());
+        assert_eq!(input_buffer.push_multiple(&[5, 6]), 0);
+        assert_eq!(input_buffer.overflow_count(), 3);
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.overflow_count(), 0);
+    }
+
+    #[test]
+    fn bytes_dropped() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        assert_eq!(input_buffer.push_multiple(&[1, 2, 3, 4, 5, 6, 7, 8]), 4);
+        assert_eq!(input_buffer.len(), 4);
+        assert_eq!(input_buffer.bytes_dropped(), 4);
+
+        assert!(input_buffer.push(9).is_err());
+        assert_eq!(input_buffer.bytes_dropped(), 5);
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.bytes_dropped(), 0);
+    }
+
+    #[test]
+    fn cursor() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[2, 10, 20, 30, 40]);
+
+        let parsed = {
+            let mut cursor = input_buffer.cursor();
+            let field_len = cursor.read_u8().unwrap() as usize;
+            assert_eq!(cursor.read_slice(field_len), Some(&[10u8, 20][..]));
+            cursor.position()
+        };
+        assert_eq!(parsed, 3);
+        assert_eq!(input_buffer.len(), 5);
+
+        let mut frame = [0u8; 3];
+        assert_eq!(input_buffer.consume(&mut frame[..parsed]), 3);
+        assert_eq!(input_buffer.as_slice(), &[30, 40]);
+    }
+
+    #[test]
+    fn replace_all() {
+        let mut buffer = [b' '; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"a b c");
+
+        assert_eq!(input_buffer.replace_all(b' ', b'_'), 2);
+        assert_eq!(input_buffer.as_slice(), b"a_b_c");
+        assert_eq!(input_buffer.len(), 5);
+    }
+
+    #[test]
+    fn lines() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"c");
+        assert_eq!(input_buffer.lines().count(), 0);
+
+        input_buffer.clear();
+        input_buffer.push_multiple(b"a\nb\nc");
+        let mut lines = input_buffer.lines();
+        assert_eq!(lines.next(), Some(&b"a"[..]));
+        assert_eq!(lines.next(), Some(&b"b"[..]));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn bit_reader() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(0b101_01010).unwrap();
+
+        let mut reader = input_buffer.bit_reader();
+        assert_eq!(reader.read_bits(3), Some(0b101));
+        assert_eq!(reader.read_bits(5), Some(0b01010));
+        assert_eq!(reader.read_bits(1), None);
+        assert_eq!(reader.bytes_read(), 1);
+        assert_eq!(input_buffer.len(), 1);
+    }
+
+    #[test]
+    fn transfer_between_buffers() {
+        let mut src_buffer = [0u8; 10];
+        let mut dst_buffer = [0u8; 4];
+        let mut src = InputBuffer::new(&mut src_buffer);
+        let mut dst = InputBuffer::new(&mut dst_buffer);
+        src.push_multiple(&[1, 2, 3, 4, 5]);
+        dst.push(0).unwrap();
+
+        assert_eq!(transfer(&mut src, &mut dst, 10), 3);
+        assert_eq!(src.as_slice(), &[4, 5]);
+        assert_eq!(dst.as_slice(), &[0, 1, 2, 3]);
+        assert!(!dst.overflown());
+
+        dst.clear();
+        assert_eq!(transfer(&mut src, &mut dst, 1), 1);
+        assert_eq!(src.as_slice(), &[5]);
+        assert_eq!(dst.as_slice(), &[4]);
+    }
+
+    #[test]
+    fn clear_zeroed() {
+        let mut buffer = [0u8; 6];
+        {
+            let mut input_buffer = InputBuffer::new(&mut buffer);
+            input_buffer.push_multiple(&[1, 2, 3, 4, 5, 6, 7]);
+            let mut read_buffer = [0u8; 2];
+            input_buffer.consume(&mut read_buffer);
+
+            input_buffer.clear_zeroed();
+            assert_eq!(input_buffer.len(), 0);
+            assert!(!input_buffer.overflown());
+        }
+        assert_eq!(buffer, [0u8; 6]);
+    }
+
+    #[test]
+    fn resize_returning_delta() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2]);
+
+        assert_eq!(input_buffer.resize_returning_delta(5), 3);
+        assert_eq!(input_buffer.resize_returning_delta(1), -4);
+        assert_eq!(input_buffer.resize_returning_delta(20), 7);
+        assert_eq!(input_buffer.len(), 8);
+    }
+
+    #[test]
+    fn find_and_contains() {
+        let mut buffer = [b'x'; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"abcb");
+
+        assert_eq!(input_buffer.find(b'b'), Some(1));
+        assert_eq!(input_buffer.find(b'x'), None);
+        assert!(input_buffer.contains(b'c'));
+        assert!(!input_buffer.contains(b'x'));
+
+        input_buffer.push(b'x').unwrap();
+        assert!(input_buffer.contains(b'x'));
+    }
+
+    #[test]
+    fn starts_and_ends_with() {
+        let mut buffer = [0u8; 20];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"GET / HTTP/1.1\r\n");
+
+        assert!(input_buffer.starts_with(b"GET "));
+        assert!(!input_buffer.starts_with(b"POST "));
+        assert!(input_buffer.ends_with(b"\r\n"));
+        assert!(!input_buffer.ends_with(b"GET / HTTP/1.1\r\n\r\n"));
+    }
+
+    #[test]
+    fn drain() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        let mut sum = 0;
+        for b in input_buffer.drain().take(3) {
+            sum += b;
+        }
+        assert_eq!(sum, 6);
+        assert_eq!(input_buffer.as_slice(), &[4, 5]);
+
+        assert_eq!(input_buffer.drain().count(), 2);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn append() {
+        let mut buffer = [0u8; 6];
+        let mut other_buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let mut other = InputBuffer::new(&mut other_buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+        other.push_multiple(&[4, 5]);
+
+        assert_eq!(input_buffer.append(&other).unwrap(), 2);
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4, 5]);
+        assert_eq!(other.len(), 2);
+
+        assert!(input_buffer.append(&other).is_err());
+        assert_eq!(input_buffer.len(), 5);
+        assert!(input_buffer.overflown());
+    }
+
+    #[test]
+    fn push_repeated() {
+        let mut buffer = [1u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[7, 7]);
+
+        assert_eq!(input_buffer.push_repeated(0, 2), 2);
+        assert!(!input_buffer.overflown());
+
+        let remaining = input_buffer.remaining_capacity();
+        assert_eq!(input_buffer.push_repeated(0, 10), remaining);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.as_slice(), &[7, 7, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn push_empty_into_full() {
+        let mut buffer = [0u8; 2];
+        let mut other_buffer = [0u8; 2];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let other = InputBuffer::new(&mut other_buffer);
+        input_buffer.push_multiple(&[1, 2]);
+
+        assert_eq!(input_buffer.push_multiple(&[]), 0);
+        assert_eq!(input_buffer.push_repeated(0, 0), 0);
+        assert_eq!(input_buffer.append(&other).unwrap(), 0);
+        assert!(!input_buffer.overflown());
+        assert_eq!(input_buffer.as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    fn try_reserve() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(1).unwrap();
+
+        assert!(input_buffer.try_reserve(3).is_ok());
+        assert!(input_buffer.try_reserve(4).is_err());
+        assert!(!input_buffer.overflown());
+        assert_eq!(input_buffer.len(), 1);
+    }
+
+    #[test]
+    fn fold_bytes() {
+        let mut buffer = [9u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        assert_eq!(input_buffer.fold_bytes(0u32, |acc, b| acc + b as u32), 6);
+        assert_eq!(input_buffer.fold_bytes(0u8, |acc, b| acc ^ b), 0);
+        assert_eq!(input_buffer.len(), 3);
+    }
+
+    #[test]
+    fn overflow_policy() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new_with_policy(&mut buffer, OverflowPolicy::Refuse);
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert_eq!(input_buffer.push_multiple(&[4, 5]), 1);
+        assert!(input_buffer.push(6).is_err());
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4]);
+        assert_eq!(input_buffer.bytes_dropped(), 2);
+
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new_with_policy(&mut buffer, OverflowPolicy::DropNewest);
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert_eq!(input_buffer.push_multiple(&[4, 5]), 1);
+        assert!(input_buffer.push(6).is_ok());
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4]);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.bytes_dropped(), 2);
+
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new_with_policy(&mut buffer, OverflowPolicy::DropOldest);
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert_eq!(input_buffer.push_multiple(&[4, 5]), 2);
+        assert_eq!(input_buffer.as_slice(), &[2, 3, 4, 5]);
+        assert!(input_buffer.push(6).is_ok());
+        assert_eq!(input_buffer.as_slice(), &[3, 4, 5, 6]);
+        assert_eq!(input_buffer.push_multiple(&[7, 8, 9, 10, 11, 12]), 4);
+        assert_eq!(input_buffer.as_slice(), &[9, 10, 11, 12]);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.bytes_dropped(), 8);
+    }
+
+    #[test]
+    fn resize_returns_len() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        assert_eq!(input_buffer.resize(2), 2);
+        assert_eq!(input_buffer.resize(10), input_buffer.capacity());
+        assert_eq!(input_buffer.len(), 4);
+    }
+
+    #[test]
+    fn first_and_last() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        assert_eq!(input_buffer.first(), None);
+        assert_eq!(input_buffer.last(), None);
+
+        input_buffer.push_multiple(&[10, 20, 30]);
+        assert_eq!(input_buffer.first(), Some(10));
+        assert_eq!(input_buffer.last(), Some(30));
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        input_buffer.push_varint(300).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[0xac, 0x02]);
+        input_buffer.push_varint(u64::MAX).unwrap();
+        assert_eq!(input_buffer.len(), 12);
+
+        assert_eq!(input_buffer.read_varint(), Ok(Some(300)));
+        assert_eq!(input_buffer.read_varint(), Ok(Some(u64::MAX)));
+        assert!(input_buffer.is_empty());
+        assert!(input_buffer.push_varint(u64::MAX).is_ok());
+        assert!(input_buffer.push_varint(u64::MAX).is_err());
+        assert_eq!(input_buffer.len(), 10);
+    }
+
+    #[test]
+    fn read_incomplete_varint() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        input_buffer.push_multiple(&[0xac]);
+        assert_eq!(input_buffer.read_varint(), Ok(None));
+        assert_eq!(input_buffer.len(), 1);
+
+        input_buffer.push(0x02).unwrap();
+        assert_eq!(input_buffer.read_varint(), Ok(Some(300)));
+    }
+
+    #[test]
+    fn read_overflowing_varint() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        input_buffer.push_multiple(&[0xff; 9]);
+        assert_eq!(input_buffer.read_varint(), Ok(None));
+        input_buffer.push(0x7f).unwrap();
+        assert_eq!(input_buffer.read_varint(), Err(VarintError::Overflow));
+        assert_eq!(input_buffer.len(), 10);
+
+        input_buffer.clear();
+        input_buffer.push_multiple(&[0x80; 9]);
+        input_buffer.push(0x81).unwrap();
+        assert_eq!(input_buffer.read_varint(), Err(VarintError::Overflow));
+        assert_eq!(input_buffer.len(), 10);
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        let mut small = [0u8; 2];
+        assert_eq!(input_buffer.snapshot(&mut small), Err(3));
+        assert_eq!(small, [0, 0]);
+
+        let mut large = [0u8; 4];
+        assert_eq!(input_buffer.snapshot(&mut large), Ok(3));
+        assert_eq!(large, [1, 2, 3, 0]);
+        assert_eq!(input_buffer.len(), 3);
+    }
+
+    #[test]
+    fn aligned_spare_capacity() {
+        let mut buffer = [0xffu8; 64];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(1).unwrap();
+
+        let remaining = input_buffer.remaining_capacity();
+        let (padding, spare) = input_buffer.aligned_spare_capacity_mut(16);
+        assert_eq!(spare.as_ptr() as usize % 16, 0);
+        assert!(!spare.is_empty());
+        assert_eq!(padding + spare.len(), remaining);
+        spare[0] = 0xaa;
+
+        input_buffer.advance(padding + 1).unwrap();
+        assert_eq!(input_buffer.last(), Some(0xaa));
+        assert_eq!(input_buffer.len(), padding + 2);
+        assert!(input_buffer.as_slice()[1..padding + 1].iter().all(|b| *b == 0));
+
+        input_buffer.resize(60);
+        assert!(input_buffer.aligned_spare_capacity_mut(64).1.len() <= 4);
+        assert!(input_buffer.advance(5).is_err());
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.len(), 60);
+    }
+
+    #[test]
+    fn random_operations_never_panic() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let mut expected_len = 0usize;
+        let mut state = 0x2545_f491u32;
+        let mut next = || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as usize
+        };
+
+        for _ in 0..10_000 {
+            let amount = next() % 24;
+            match next() % 5 {
+                0 => {
+                    if input_buffer.push(amount as u8).is_ok() {
+                        expected_len += 1;
+                    }
+                }
+                1 => {
+                    let values = [0xa5u8; 24];
+                    expected_len += input_buffer.push_multiple(&values[..amount]);
+                }
+                2 => {
+                    let mut output = [0u8; 24];
+                    expected_len -= input_buffer.consume(&mut output[..amount]);
+                }
+                3 => {
+                    expected_len = input_buffer.resize(amount);
+                }
+                _ => {
+                    let mut output = [0u8; 24];
+                    if input_buffer.read_exact(&mut output[..amount]) {
+                        expected_len -= amount;
+                    }
+                }
+            }
+            assert_eq!(input_buffer.len(), expected_len);
+            assert!(input_buffer.len() <= input_buffer.capacity());
+        }
+    }
+
+    #[test]
+    fn consume_matching() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"123abc");
+
+        let mut digits = [0u8; 8];
+        assert_eq!(input_buffer.consume_matching(|b| b.is_ascii_digit(), &mut digits), 3);
+        assert_eq!(&digits[..3], b"123");
+        assert_eq!(input_buffer.as_slice(), b"abc");
+
+        let mut small = [0u8; 2];
+        assert_eq!(input_buffer.consume_matching(|b| b.is_ascii_alphabetic(), &mut small), 2);
+        assert_eq!(input_buffer.as_slice(), b"c");
+    }
+
+    #[test]
+    fn reverse() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+
+        input_buffer.reverse();
+        let mut output = [0u8; 4];
+        assert_eq!(input_buffer.consume(&mut output), 4);
+        assert_eq!(output, [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn split_to() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+
+        let mut output = [0u8; 4];
+        assert_eq!(input_buffer.split_to(2, &mut output), Ok(2));
+        assert_eq!(&output[..2], &[1, 2]);
+        assert_eq!(input_buffer.as_slice(), &[3, 4]);
+
+        assert_eq!(input_buffer.split_to(5, &mut output), Err(2));
+        assert_eq!(input_buffer.split_to(2, &mut output[..1]), Err(2));
+        assert_eq!(input_buffer.as_slice(), &[3, 4]);
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_format() {
+        fn assert_format<T: defmt::Format>(_: &T) {}
+        let mut buffer = [0u8; 4];
+        let input_buffer = InputBuffer::new(&mut buffer);
+        assert_format(&input_buffer);
+    }
+
+    #[test]
+    fn compact_if_below() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+        let mut output = [0u8; 2];
+        input_buffer.consume(&mut output);
+
+        assert!(!input_buffer.compact_if_below(input_buffer.capacity()));
+        assert_eq!(input_buffer.as_slice(), &[3, 4]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn consume_to_vec() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        assert_eq!(input_buffer.consume_to_vec(2), alloc::vec![1, 2]);
+        assert_eq!(input_buffer.consume_to_vec(10), alloc::vec![3]);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn high_watermark() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn on_high_watermark() {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.set_high_watermark(4, on_high_watermark);
+
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+        input_buffer.push(4).unwrap();
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        input_buffer.push_multiple(&[5, 6]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+        let mut output = [0u8; 3];
+        input_buffer.consume(&mut output);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        input_buffer.push(7).unwrap();
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn high_watermark_drop_oldest() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn on_high_watermark() {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new_with_policy(&mut buffer, OverflowPolicy::DropOldest);
+        input_buffer.set_high_watermark(4, on_high_watermark);
+
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        input_buffer.push(5).unwrap();
+        input_buffer.push(6).unwrap();
+        input_buffer.push(7).unwrap();
+        input_buffer.push_multiple(&[8, 9]);
+        assert_eq!(input_buffer.as_slice(), &[6, 7, 8, 9]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn array_chunks() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5, 6, 7]);
+
+        let mut chunks = input_buffer.array_chunks::<2>();
+        assert_eq!(chunks.next(), Some(&[1, 2]));
+        assert_eq!(chunks.next(), Some(&[3, 4]));
+        assert_eq!(chunks.next(), Some(&[5, 6]));
+        assert_eq!(chunks.next(), None);
+    }
+
+    #[test]
+    fn xor_mask() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"Hello");
+        let key = [0x37, 0xfa, 0x21, 0x3d];
+
+        input_buffer.xor_mask(&key);
+        assert_eq!(input_buffer.as_slice(), &[0x7f, 0x9f, 0x4d, 0x51, 0x58]);
+        input_buffer.xor_mask(&key);
+        assert_eq!(input_buffer.as_slice(), b"Hello");
+        input_buffer.xor_mask(&[]);
+        assert_eq!(input_buffer.as_slice(), b"Hello");
+    }
+
+    #[test]
+    fn consume_up_to() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+
+        let mut output = [0u8; 5];
+        assert_eq!(input_buffer.consume_up_to(2, &mut output), 2);
+        assert_eq!(&output[..2], &[1, 2]);
+        assert_eq!(input_buffer.len(), 2);
+        assert_eq!(input_buffer.consume_up_to(10, &mut output), 2);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn push_multiple_best_effort() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(1).unwrap();
+
+        assert_eq!(input_buffer.push_multiple_best_effort(&[2, 3, 4, 5, 6]), 3);
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4]);
+        assert!(!input_buffer.overflown());
+        assert_eq!(input_buffer.bytes_dropped(), 0);
+    }
+
+    #[test]
+    fn consume_cursor() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[0x7e, 0x01, 0x02, 0xaa, 0xbb]);
+
+        let header = input_buffer.consume_cursor(|cursor| {
+            let marker = cursor.read_u8()?;
+            let length = cursor.read_slice(2)?;
+            Some((marker, u16::from_be_bytes([length[0], length[1]])))
+        });
+        assert_eq!(header, Some((0x7e, 0x0102)));
+        assert_eq!(input_buffer.as_slice(), &[0xaa, 0xbb]);
+
+        let missing = input_buffer.consume_cursor(|cursor| cursor.read_slice(3).map(|s| s[0]));
+        assert_eq!(missing, None);
+        assert_eq!(input_buffer.len(), 2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn hex_dump() {
+        let mut buffer = [0u8; 20];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"AB");
+
+        let dump = input_buffer.hex_dump();
+        assert!(dump.contains("41 42"));
+        assert!(dump.contains("AB"));
+        assert_eq!(dump.lines().count(), 1);
+
+        input_buffer.push_multiple(b"0123456789abcdef\n");
+        let dump = input_buffer.hex_dump();
+        assert_eq!(dump.lines().count(), 2);
+        assert!(dump.contains("00000010: 65 66 0a"));
+        assert!(dump.ends_with("ef.\n"));
+    }
+
+    #[test]
+    fn rfind() {
+        let mut buffer = [b'/'; 20];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"/usr/lib/file");
+
+        assert_eq!(input_buffer.rfind(b'/'), Some(8));
+        assert_eq!(input_buffer.rfind(b'x'), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn drain_exact_to_writer() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[3, 1, 2, 3, 9]);
+
+        let mut writer = std::vec::Vec::new();
+        assert!(input_buffer.drain_exact_to_writer(4, &mut writer).unwrap());
+        assert_eq!(writer, [3, 1, 2, 3]);
+        assert_eq!(input_buffer.as_slice(), &[9]);
+
+        assert!(!input_buffer.drain_exact_to_writer(2, &mut writer).unwrap());
+        assert_eq!(writer.len(), 4);
+        assert_eq!(input_buffer.len(), 1);
+
+        let mut full = [0u8; 0];
+        assert!(input_buffer.drain_exact_to_writer(1, &mut &mut full[..]).is_err());
         assert_eq!(input_buffer.len(), 1);
+    }
+
+    #[test]
+    fn huge_counts_do_not_wrap() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2]);
+
+        assert!(input_buffer.advance(usize::MAX).is_err());
+        assert!(input_buffer.try_reserve(usize::MAX).is_err());
+        assert_eq!(input_buffer.len(), 2);
+        assert_eq!(input_buffer.push_repeated(0, usize::MAX), 2);
+        assert_eq!(input_buffer.len(), 4);
+        assert!(input_buffer.advance(usize::MAX).is_err());
+        assert_eq!(input_buffer.bytes_dropped(), usize::MAX);
+        assert_eq!(input_buffer.overflow_count(), 3);
+    }
+
+    #[test]
+    fn eq_compares_buffered_data() {
+        let mut small = [0u8; 8];
+        let mut large = [1u8; 16];
+        let mut lhs = InputBuffer::new(&mut small);
+        let mut rhs = InputBuffer::new(&mut large);
+        lhs.push_multiple(&[1, 2, 3]);
+        rhs.push_multiple(&[1, 2, 3]);
+        assert!(lhs == rhs);
+
+        rhs.push(4).unwrap();
+        assert!(lhs != rhs);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_drain() {
+        let mut buffer = [9u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        let drained: alloc::vec::Vec<u8> = input_buffer.into_drain().collect();
+        assert_eq!(drained, alloc::vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn min_fill() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        assert!(!input_buffer.ready());
+        input_buffer.push(1).unwrap();
+        assert!(input_buffer.ready());
+
+        input_buffer.set_min_fill(4);
+        assert!(!input_buffer.ready());
+        input_buffer.push_multiple(&[2, 3]);
+        assert!(input_buffer.has_at_least(3));
+        assert!(!input_buffer.ready());
+        input_buffer.push(4).unwrap();
+        assert!(input_buffer.ready());
+    }
+
+    #[test]
+    fn read_line_crlf() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"GET /\r\nHost");
+
+        let mut line = [0u8; 8];
+        assert_eq!(input_buffer.read_line_crlf(&mut line), Some(5));
+        assert_eq!(&line[..5], b"GET /");
+        assert_eq!(input_buffer.as_slice(), b"Host");
+
+        input_buffer.push_multiple(b":\n");
+        assert_eq!(input_buffer.read_line_crlf(&mut line), None);
+        input_buffer.push_multiple(b"x\r\n");
+        assert_eq!(input_buffer.read_line_crlf(&mut line), Some(7));
+        assert_eq!(&line[..7], b"Host:\nx");
+        assert!(input_buffer.is_empty());
+
+        input_buffer.push_multiple(b"too long\r\nok\r\n");
+        assert_eq!(input_buffer.read_line_crlf(&mut line[..2]), Some(8));
+        assert_eq!(&line[..2], b"to");
+        assert_eq!(input_buffer.read_line_crlf(&mut line), Some(2));
+        assert_eq!(&line[..2], b"ok");
+    }
+
+    #[test]
+    fn bytes_until() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"abc,def");
+
+        assert_eq!(input_buffer.bytes_until(b','), Some(3));
+        assert_eq!(input_buffer.bytes_until(b'a'), Some(0));
+        assert_eq!(input_buffer.bytes_until(b';'), None);
+    }
+
+    #[test]
+    fn rebase_into() {
+        let mut small = [0u8; 4];
+        let mut large = [0u8; 8];
+        let mut too_small = [0u8; 2];
+        let mut input_buffer = InputBuffer::new(&mut small);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        let input_buffer = match input_buffer.rebase_into(&mut too_small) {
+            Ok(_) => panic!("rebased into a too small buffer"),
+            Err(original) => original,
+        };
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4]);
+
+        let mut input_buffer = match input_buffer.rebase_into(&mut large) {
+            Ok(rebased) => rebased,
+            Err(_) => panic!("failed to rebase into a larger buffer"),
+        };
+        assert_eq!(input_buffer.capacity(), 8);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.push_multiple(&[5, 6]), 2);
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn is_contiguous() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        assert!(input_buffer.is_contiguous());
+        input_buffer.push_multiple(&[1, 2, 3]);
+        let mut output = [0u8; 1];
+        input_buffer.consume(&mut output);
+        assert!(input_buffer.is_contiguous());
+    }
+
+    #[test]
+    fn push_multiple_detailed() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        assert_eq!(input_buffer.push_multiple_detailed(&[1, 2, 3]), Ok(3));
+        assert_eq!(input_buffer.push_multiple_detailed(&[0; 8]), Err((5, 3)));
+        assert_eq!(input_buffer.len(), 8);
+        assert!(input_buffer.overflown());
+    }
+
+    #[test]
+    fn keep_last() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        input_buffer.keep_last(2);
+        assert_eq!(input_buffer.as_slice(), &[4, 5]);
+        input_buffer.keep_last(3);
+        assert_eq!(input_buffer.as_slice(), &[4, 5]);
+        input_buffer.keep_last(0);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn position() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"123x");
+
+        assert_eq!(input_buffer.position(|b| !b.is_ascii_digit()), Some(3));
+        assert_eq!(input_buffer.position(|b| b == b'y'), None);
+    }
+
+    #[test]
+    fn into_parts() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        let (backing, len) = input_buffer.into_parts();
+        assert_eq!(backing.len(), 8);
+        assert_eq!(&backing[..len], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn write_at() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[0, 0, 1, 2, 3]);
+
+        input_buffer.write_at(0, &[0, 5]).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[0, 5, 1, 2, 3]);
+        assert!(input_buffer.write_at(4, &[9, 9]).is_err());
+        assert!(input_buffer.write_at(6, &[]).is_err());
+        assert_eq!(input_buffer.as_slice(), &[0, 5, 1, 2, 3]);
+        assert!(!input_buffer.overflown());
+    }
+
+    #[test]
+    fn extend_from_iter() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        assert_eq!(input_buffer.extend_from_iter(0..3u8), 3);
+        assert_eq!(input_buffer.as_slice(), &[0, 1, 2]);
+        assert!(!input_buffer.overflown());
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.extend_from_iter(0..10u8), 10);
+        assert!(!input_buffer.overflown());
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.extend_from_iter(0..100u8), 10);
+        assert_eq!(input_buffer.last(), Some(9));
+        assert!(input_buffer.overflown());
+    }
+
+    #[test]
+    fn peek_tail() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let mut output = [0u8; 2];
+        assert_eq!(input_buffer.peek_tail(&mut output), 0);
+
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+        assert_eq!(input_buffer.peek_tail(&mut output), 2);
+        assert_eq!(output, [3, 4]);
+        assert_eq!(input_buffer.len(), 4);
+
+        let mut large = [0u8; 6];
+        assert_eq!(input_buffer.peek_tail(&mut large), 4);
+        assert_eq!(&large[..4], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn split() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn on_high_watermark() {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut buffer = [0u8; 5];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.set_high_watermark(4, on_high_watermark);
+        input_buffer.push_multiple(&[1, 2]);
+
+        {
+            let (front, mut tail) = input_buffer.split();
+            assert_eq!(front, &[1, 2]);
+            tail.push(front[0] + 2).unwrap();
+            assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+            assert_eq!(tail.push_multiple(&[front[1] + 2, 5, 6]), 2);
+            assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+            assert!(tail.push(7).is_err());
+            assert_eq!(front, &[1, 2]);
+        }
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4, 5]);
 
-        let consumed = input_buffer.consume(&mut consume_buffer);
-        assert_eq!(consumed, 1);
-        assert_eq!(consume_buffer[0], 6);
-        assert_eq!(input_buffer.len(), 0);
+        let mut output = [0u8; 3];
+        input_buffer.consume(&mut output);
+        let (_, mut tail) = input_buffer.split();
+        tail.push_multiple(&[6, 7]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
 
-        input_buffer.push(4).unwrap();
-        input_buffer.push(5).unwrap();
-        input_buffer.push(6).unwrap();
-        let consumed = input_buffer.consume(&mut consume_buffer);
-        assert_eq!(consumed, 2);
-        assert_eq!(consume_buffer[0], 4);
-        assert_eq!(consume_buffer[1], 5);
+    #[test]
+    fn split_tracks_peak_demand() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2]);
+
+        let (_, mut tail) = input_buffer.split();
+        tail.push(3).unwrap();
+        assert_eq!(tail.push_multiple(&[4, 5, 6, 7, 8]), 5);
+
+        assert_eq!(input_buffer.len(), 8);
+        assert_eq!(input_buffer.peak_demand(), 8);
+        assert_eq!(input_buffer.suggested_capacity(), 8);
+    }
+
+    #[test]
+    fn try_consume() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let mut output = [0u8; 2];
+        assert_eq!(input_buffer.try_consume(&mut output), None);
+
+        input_buffer.push(1).unwrap();
+        assert_eq!(input_buffer.try_consume(&mut output[..0]), Some(0));
+        assert_eq!(input_buffer.try_consume(&mut output), Some(1));
+        assert_eq!(input_buffer.try_consume(&mut output), None);
+    }
+
+    #[test]
+    fn swap() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        input_buffer.swap(0, 2);
+        assert_eq!(input_buffer.as_slice(), &[3, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_outside_buffered_data() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+        input_buffer.swap(0, 3);
+    }
+
+    #[test]
+    fn fill() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+
+        input_buffer.fill(0xff);
+        let mut output = [0u8; 6];
+        assert_eq!(input_buffer.consume(&mut output), 4);
+        assert_eq!(&output[..4], &[0xff; 4]);
+    }
+
+    #[test]
+    fn ensure_contiguous_write() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+        let mut output = [0u8; 2];
+        input_buffer.consume(&mut output);
+
+        assert!(input_buffer.ensure_contiguous_write(4));
+        assert_eq!(input_buffer.spare_capacity_mut().len(), 4);
+        assert!(!input_buffer.ensure_contiguous_write(5));
+        assert_eq!(input_buffer.as_slice(), &[3, 4]);
+    }
+
+    #[test]
+    fn consume_array() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(input_buffer.consume_array::<4>(), Some([1, 2, 3, 4]));
         assert_eq!(input_buffer.len(), 1);
 
-        input_buffer.push(7).unwrap();
-        let consumed = input_buffer.consume(&mut consume_buffer);
-        assert_eq!(consumed, 2);
-        assert_eq!(consume_buffer[0], 6);
-        assert_eq!(consume_buffer[1], 7);
-        assert_eq!(input_buffer.len(), 0);
+        input_buffer.push_multiple(&[6, 7]);
+        assert_eq!(input_buffer.consume_array::<4>(), None);
+        assert_eq!(input_buffer.as_slice(), &[5, 6, 7]);
+    }
+
+    #[test]
+    fn as_slices() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        let (front, back) = input_buffer.as_slices();
+        assert_eq!(front, &[1, 2, 3]);
+        assert!(back.is_empty());
+        assert_eq!(front.len() + back.len(), input_buffer.len());
+    }
+
+    #[test]
+    fn consume_into_all() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        let mut first = [0u8; 2];
+        let mut second = [0u8; 2];
+        assert_eq!(input_buffer.consume_into_all(&mut [&mut first, &mut second]), 4);
+        assert_eq!(first, [1, 2]);
+        assert_eq!(second, [3, 4]);
+        assert_eq!(input_buffer.as_slice(), &[5]);
+
+        assert_eq!(input_buffer.consume_into_all(&mut [&mut first, &mut second]), 1);
+        assert_eq!(first, [5, 2]);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn push_vectored() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        assert_eq!(input_buffer.push_vectored(&[b"AB", b"CDE"]), 4);
+        assert_eq!(input_buffer.as_slice(), b"ABCD");
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.overflow_count(), 1);
+        assert_eq!(input_buffer.bytes_dropped(), 1);
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.push_vectored(&[b"A", b"", b"BC"]), 3);
+        assert!(!input_buffer.overflown());
+    }
+
+    #[test]
+    fn suggested_capacity() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[0; 5]);
+        let mut output = [0u8; 5];
+        input_buffer.consume(&mut output);
+
+        assert_eq!(input_buffer.peak_demand(), 5);
+        assert_eq!(input_buffer.suggested_capacity(), 8);
+
+        input_buffer.push_multiple(&[0; 14]);
+        input_buffer.push_multiple(&[0; 6]);
+        assert_eq!(input_buffer.peak_demand(), 20);
+        assert_eq!(input_buffer.suggested_capacity(), 32);
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.peak_demand(), 0);
+    }
+
+    #[test]
+    fn clear_and_fill() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        input_buffer.clear_and_fill(3, 0xaa);
+        assert!(!input_buffer.overflown());
+        let mut output = [0u8; 4];
+        assert_eq!(input_buffer.consume(&mut output), 3);
+        assert_eq!(&output[..3], &[0xaa; 3]);
+
+        input_buffer.clear_and_fill(10, 0);
+        assert_eq!(input_buffer.len(), 4);
+    }
+
+    #[test]
+    fn push_array() {
+        let mut buffer = [0u8; 7];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        input_buffer.push_array(1234u32.to_le_bytes()).unwrap();
+        assert_eq!(input_buffer.as_slice(), &1234u32.to_le_bytes());
+        assert!(input_buffer.push_array([1, 2, 3, 4]).is_err());
+        assert_eq!(input_buffer.len(), 4);
+        assert!(input_buffer.overflown());
+    }
+
+    #[test]
+    fn consume_if() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let frame = |data: &[u8]| match data.first() {
+            Some(0x7e) => Some(3),
+            _ => None,
+        };
+        input_buffer.push_multiple(&[0x01, 0x7e, 0x02, 0x03]);
+
+        let mut output = [0u8; 4];
+        assert_eq!(input_buffer.consume_if(frame, &mut output), None);
+        assert_eq!(input_buffer.len(), 4);
+
+        input_buffer.consume(&mut output[..1]);
+        assert_eq!(input_buffer.consume_if(frame, &mut output), Some(3));
+        assert_eq!(&output[..3], &[0x7e, 0x02, 0x03]);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn make_front_room() {
+        let mut buffer = [9u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        input_buffer.make_front_room(2).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[0, 0, 1, 2, 3]);
+        input_buffer.write_at(0, &[0, 3]).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[0, 3, 1, 2, 3]);
+
+        assert!(input_buffer.make_front_room(2).is_err());
+        assert_eq!(input_buffer.as_slice(), &[0, 3, 1, 2, 3]);
+        assert!(input_buffer.overflown());
+    }
+
+    #[test]
+    fn backlog() {
+        let mut buffer = [0u8; 10];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        assert_eq!(input_buffer.backlog_ratio_permille(), 0);
+
+        input_buffer.push_multiple(&[0; 5]);
+        assert_eq!(input_buffer.backlog(), 5);
+        assert_eq!(input_buffer.backlog_ratio_permille(), 500);
+
+        input_buffer.push_multiple(&[0; 5]);
+        assert_eq!(input_buffer.backlog_ratio_permille(), 1000);
+
+        let mut empty: [u8; 0] = [];
+        assert_eq!(InputBuffer::new(&mut empty).backlog_ratio_permille(), 1000);
+    }
+
+    #[test]
+    fn peek_scalars() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(0x34).unwrap();
+        assert_eq!(input_buffer.peek_u8(), Some(0x34));
+        assert_eq!(input_buffer.peek_u16_le(), None);
+
+        input_buffer.push_multiple(&[0x12, 0xff]);
+        assert_eq!(input_buffer.peek_u16_le(), Some(0x1234));
+        assert_eq!(input_buffer.peek_u16_be(), Some(0x3412));
+        assert_eq!(input_buffer.peek_u32_le(), None);
+
+        input_buffer.push(0x00).unwrap();
+        assert_eq!(input_buffer.peek_u32_le(), Some(0x00ff_1234));
+        assert_eq!(input_buffer.peek_u32_be(), Some(0x3412_ff00));
+        assert_eq!(input_buffer.len(), 4);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn freeze_range() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        let frozen = input_buffer.freeze_range(0..3);
+        input_buffer.clear();
+        input_buffer.push_multiple(&[9, 9, 9]);
+        assert_eq!(&*frozen, &[1, 2, 3]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    #[should_panic]
+    fn freeze_range_past_len() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2]);
+        input_buffer.freeze_range(0..3);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new_with_rewind(&mut buffer, 3);
+        assert_eq!(input_buffer.capacity(), 5);
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+
+        let mut output = [0u8; 3];
+        input_buffer.consume(&mut output);
+        assert_eq!(input_buffer.rewind(2), 2);
+        assert_eq!(input_buffer.as_slice(), &[2, 3, 4, 5]);
+
+        input_buffer.consume(&mut output[..2]);
+        input_buffer.consume(&mut output[..1]);
+        input_buffer.consume(&mut output[..1]);
+        assert_eq!(input_buffer.as_slice(), &[]);
+        assert_eq!(input_buffer.rewind(10), 3);
+        assert_eq!(input_buffer.as_slice(), &[3, 4, 5]);
+        assert_eq!(input_buffer.rewind(1), 0);
+
+        input_buffer.consume(&mut output[..1]);
+        input_buffer.clear();
+        assert_eq!(input_buffer.rewind(1), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rewind_forgets_history_when_dropping() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new_with_rewind_and_policy(&mut buffer, 3, OverflowPolicy::DropOldest);
+        let mut output = [0u8; 3];
+
+        input_buffer.push_multiple(&[1, 2, 3, 4, 5]);
+        input_buffer.consume(&mut output[..1]);
+        input_buffer.push_multiple(&[6, 7]);
+        assert_eq!(input_buffer.as_slice(), &[3, 4, 5, 6, 7]);
+        assert_eq!(input_buffer.rewind(3), 0);
+
+        input_buffer.consume(&mut output[..1]);
+        input_buffer.keep_last(2);
+        assert_eq!(input_buffer.rewind(3), 0);
+        assert_eq!(input_buffer.as_slice(), &[6, 7]);
+
+        input_buffer.consume(&mut output[..2]);
+        input_buffer.push_multiple(b"  a");
+        input_buffer.trim();
+        assert_eq!(input_buffer.rewind(3), 0);
+        assert_eq!(input_buffer.as_slice(), b"a");
+
+        input_buffer.consume(&mut output[..1]);
+        input_buffer.push_multiple(b"b ");
+        input_buffer.trim();
+        input_buffer.keep_last(5);
+        assert_eq!(input_buffer.rewind(3), 1);
+        assert_eq!(input_buffer.as_slice(), b"ab");
+    }
+
+    #[test]
+    fn rewind_without_history() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2]);
+        let mut output = [0u8; 2];
+        input_buffer.consume(&mut output);
+
+        assert_eq!(input_buffer.rewind(2), 0);
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn split_mut() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        let (data, spare) = input_buffer.split_mut();
+        for (byte, out) in data.iter_mut().zip(spare.iter_mut()) {
+            *byte *= 2;
+            *out = *byte + 1;
+        }
+        input_buffer.advance(3).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[2, 4, 6, 3, 5, 7]);
+    }
+
+    #[test]
+    fn occupancy_histogram() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        input_buffer.sample_occupancy();
+        input_buffer.push_multiple(&[0; 3]);
+        input_buffer.sample_occupancy();
+        input_buffer.push_multiple(&[0; 5]);
+        input_buffer.sample_occupancy();
+        input_buffer.push_multiple(&[0; 8]);
+        input_buffer.sample_occupancy();
+        input_buffer.clear();
+        input_buffer.sample_occupancy();
+
+        assert_eq!(input_buffer.occupancy_buckets(), [2, 1, 0, 0, 1, 0, 0, 1]);
+        input_buffer.reset_occupancy();
+        assert_eq!(input_buffer.occupancy_buckets(), [0; 8]);
+    }
+
+    #[test]
+    fn push_or_return() {
+        let mut buffer = [0u8; 2];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+
+        assert_eq!(input_buffer.push_or_return(1), Ok(1));
+        assert_eq!(input_buffer.push_or_return(2), Ok(2));
+        assert_eq!(input_buffer.push_or_return(3), Err(3));
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    fn consume_through() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"dataENDmore");
+
+        assert_eq!(input_buffer.find_subslice(b"END"), Some(4));
+        assert_eq!(input_buffer.find_subslice(b"NOPE"), None);
+
+        let mut output = [0u8; 16];
+        assert_eq!(input_buffer.consume_through(b"XYZ", &mut output), None);
+        assert_eq!(input_buffer.len(), 11);
+
+        assert_eq!(input_buffer.consume_through(b"END", &mut output), Some(7));
+        assert_eq!(&output[..7], b"dataEND");
+        assert_eq!(input_buffer.as_slice(), b"more");
+
+        assert_eq!(input_buffer.consume_through(b"mo", &mut output), Some(2));
+        assert_eq!(input_buffer.as_slice(), b"re");
+
+        input_buffer.push_multiple(b"abcEND");
+        let mut small = [0u8; 2];
+        assert_eq!(input_buffer.consume_through(b"END", &mut small), Some(8));
+        assert_eq!(&small, b"re");
+        assert!(input_buffer.is_empty());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn consume_line_lossy() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"hello\nworld");
+
+        assert_eq!(input_buffer.consume_line_lossy().as_deref(), Some("hello"));
+        assert_eq!(input_buffer.as_slice(), b"world");
+        assert_eq!(input_buffer.consume_line_lossy(), None);
+        assert_eq!(input_buffer.as_slice(), b"world");
+    }
+
+    #[test]
+    fn footprint() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        assert_eq!(input_buffer.footprint(), (0, 8));
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert_eq!(input_buffer.footprint(), (input_buffer.len(), input_buffer.capacity()));
+        assert_eq!(input_buffer.footprint(), (3, 8));
+    }
+
+    #[test]
+    fn set_contents() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[9, 9, 9, 9]);
+
+        assert!(input_buffer.set_contents(&[1, 2, 3]).is_ok());
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3]);
+        assert!(!input_buffer.overflown());
+
+        assert!(input_buffer.set_contents(&[1, 2, 3, 4, 5]).is_err());
+        assert!(input_buffer.is_empty());
+        assert!(input_buffer.overflown());
+    }
+
+    #[test]
+    fn frame_offsets() {
+        let mut buffer = [0u8; 16];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"a\nbb\nccc");
+
+        let mut offsets = [0usize; 4];
+        assert_eq!(input_buffer.frame_offsets(b'\n', &mut offsets), 2);
+        assert_eq!(&offsets[..2], &[1, 4]);
+        assert_eq!(input_buffer.len(), 8);
+
+        let mut one = [0usize; 1];
+        assert_eq!(input_buffer.frame_offsets(b'\n', &mut one), 1);
+        assert_eq!(one, [1]);
+    }
+
+    #[test]
+    fn assume_filled() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(1).unwrap();
+        input_buffer.spare_capacity_mut()[..2].copy_from_slice(&[2, 3]);
+
+        input_buffer.assume_filled(3);
+        let mut output = [0u8; 4];
+        assert_eq!(input_buffer.consume(&mut output), 3);
+        assert_eq!(&output[..3], &[1, 2, 3]);
+
+        input_buffer.assume_filled(10);
+        assert_eq!(input_buffer.len(), 4);
+        assert!(!input_buffer.overflown());
+    }
+
+    #[test]
+    fn ct_eq() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[0xde, 0xad, 0xbe, 0xef]);
+
+        assert!(input_buffer.ct_eq(&[0xde, 0xad, 0xbe, 0xef]));
+        assert!(!input_buffer.ct_eq(&[0xde, 0xad, 0xbe, 0xee]));
+        assert!(!input_buffer.ct_eq(&[0xdf, 0xad, 0xbe, 0xef]));
+        assert!(!input_buffer.ct_eq(&[0xde, 0xad, 0xbe]));
+    }
+
+    #[test]
+    fn trim() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"  abc  ");
+        input_buffer.trim();
+        assert_eq!(input_buffer.as_slice(), b"abc");
+        assert_eq!(input_buffer.len(), 3);
+
+        input_buffer.set_contents(b" \t\r\n ").unwrap();
+        input_buffer.trim();
+        assert!(input_buffer.is_empty());
+    }
+
+    #[test]
+    fn push_multiple_odd_lengths() {
+        let mut source = [0u8; 4099];
+        for (i, byte) in source.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        for offset in 0..4 {
+            for len in [1, 3, 15, 17, 63, 4093] {
+                let mut buffer = [0u8; 4100];
+                let mut input_buffer = InputBuffer::new(&mut buffer);
+                input_buffer.push_multiple(&source[..offset]);
+                assert_eq!(input_buffer.push_multiple(&source[offset..offset + len]), len);
+                assert_eq!(input_buffer.as_slice(), &source[..offset + len]);
+            }
+        }
+    }
+
+    #[test]
+    fn consume_array_header() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[0x01, 0x00, 0x34, 0x12, 0xaa]);
+        assert_eq!(input_buffer.consume_array::<6>(), None);
+        assert_eq!(input_buffer.len(), 5);
+
+        input_buffer.push_multiple(&[0xbb, 0xcc]);
+        let header: [u8; 6] = input_buffer.consume_array().unwrap();
+        assert_eq!(u16::from_le_bytes([header[0], header[1]]), 1);
+        assert_eq!(u16::from_le_bytes([header[2], header[3]]), 0x1234);
+        assert_eq!(&header[4..], &[0xaa, 0xbb]);
+        assert_eq!(input_buffer.as_slice(), &[0xcc]);
+    }
+
+    #[test]
+    fn last_overflow_seq() {
+        let mut buffer = [0u8; 2];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.mark_overflow_seq(1);
+        input_buffer.push_multiple(&[1, 2]);
+        assert_eq!(input_buffer.last_overflow_seq(), None);
+
+        input_buffer.mark_overflow_seq(7);
+        assert!(input_buffer.push(3).is_err());
+        assert_eq!(input_buffer.last_overflow_seq(), Some(7));
+
+        input_buffer.mark_overflow_seq(8);
+        assert_eq!(input_buffer.last_overflow_seq(), Some(7));
+        input_buffer.push_multiple(&[4, 5]);
+        assert_eq!(input_buffer.last_overflow_seq(), Some(8));
+
+        input_buffer.clear();
+        assert_eq!(input_buffer.last_overflow_seq(), None);
+    }
+
+    #[test]
+    fn splice() {
+        let mut buffer = [0u8; 6];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3, 4]);
+
+        input_buffer.splice(1..3, &[9, 9, 9]).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[1, 9, 9, 9, 4]);
+
+        input_buffer.splice(1..4, &[8]).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[1, 8, 4]);
+
+        input_buffer.insert_at(3, &[5, 6]).unwrap();
+        input_buffer.insert_at(0, &[0]).unwrap();
+        assert_eq!(input_buffer.as_slice(), &[0, 1, 8, 4, 5, 6]);
+        assert!(!input_buffer.overflown());
+
+        assert!(input_buffer.insert_at(2, &[7]).is_err());
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.peak_demand(), 7);
+        assert!(input_buffer.splice(0..4, &[7; 5]).is_err());
+        assert_eq!(input_buffer.peak_demand(), 7);
+        assert_eq!(input_buffer.as_slice(), &[0, 1, 8, 4, 5, 6]);
+        assert!(input_buffer.splice(0..2, &[7, 7]).is_ok());
+        assert_eq!(input_buffer.as_slice(), &[7, 7, 8, 4, 5, 6]);
+    }
+
+    #[test]
+    fn byte_histogram() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(b"aabbc");
+
+        let mut histogram = [0u32; 256];
+        input_buffer.byte_histogram(&mut histogram);
+        assert_eq!(histogram[b'a' as usize], 2);
+        assert_eq!(histogram[b'b' as usize], 2);
+        assert_eq!(histogram[b'c' as usize], 1);
+        assert_eq!(histogram.iter().sum::<u32>(), 5);
+        assert_eq!(input_buffer.len(), 5);
+    }
+
+    #[test]
+    fn advance_clamped() {
+        let mut buffer = [0u8; 4];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.spare_capacity_mut().copy_from_slice(&[1, 2, 3, 4]);
+
+        assert_eq!(input_buffer.advance_clamped(3), 3);
+        assert_eq!(input_buffer.advance_clamped(5), 1);
+        assert_eq!(input_buffer.advance_clamped(1), 0);
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4]);
+        assert!(!input_buffer.overflown());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn read_buf() {
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push(1).unwrap();
+
+        let mut read_buf = input_buffer.read_buf();
+        assert_eq!(read_buf.remaining(), 7);
+        read_buf.put_slice(&[2, 3, 4]);
+        let filled = read_buf.filled().len();
+
+        assert!(input_buffer.commit_read_buf(filled).is_ok());
+        assert_eq!(input_buffer.as_slice(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn consume_keeps_overflow_flag() {
+        let mut buffer = [0u8; 2];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        let mut output = [0u8; 8];
+
+        input_buffer.push_multiple(&[1]);
+        assert_eq!(input_buffer.consume(&mut output), 1);
+        assert_eq!(input_buffer.consume(&mut output), 0);
+        assert!(!input_buffer.overflown());
+
+        input_buffer.push_multiple(&[1, 2, 3]);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.consume(&mut output[..1]), 1);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.consume(&mut output), 1);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.consume(&mut output), 0);
+        assert!(input_buffer.overflown());
+        assert_eq!(input_buffer.overflow_count(), 1);
+    }
+}
@@ -6,4 +6,13 @@
 
 #![no_std]
 
-pub mod input_buffer;
\ No newline at end of file
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod bit_reader;
+pub mod input_buffer;
+pub mod read_cursor;
+#[cfg(feature = "futures")]
+pub mod stream;
\ No newline at end of file
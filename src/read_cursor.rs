@@ -0,0 +1,92 @@
+//          Copyright Andreas Wass 2004 - 2020.
+// Distributed under the Boost Software License, Version 1.0.
+//    (See accompanying file LICENSE or copy at
+//          https://www.boost.org/LICENSE_1_0.txt)
+
+/// ReadCursor is a movable read position over borrowed data.
+///
+/// Reading through a cursor never changes the data it was
+/// created from, so it can be used to speculatively parse
+/// the content of an `InputBuffer` before consuming it.
+pub struct ReadCursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ReadCursor<'a> {
+    /// Returns a ReadCursor positioned at the start of `data`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` the data to read from.
+    pub fn new(data: &'a [u8]) -> ReadCursor<'a> {
+        ReadCursor {
+            data,
+            position: 0,
+        }
+    }
+
+    /// Reads one byte and advances the position.
+    ///
+    /// Returns `None` if no data remains.
+    pub fn read_u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(value)
+    }
+
+    /// Reads `count` bytes and advances the position.
+    ///
+    /// Returns `None`, without moving the position, if fewer than
+    /// `count` bytes remain.
+    pub fn read_slice(&mut self, count: usize) -> Option<&'a [u8]> {
+        if count > self.remaining() {
+            return None;
+        }
+        let start = self.position;
+        self.position += count;
+        Some(&self.data[start..self.position])
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves the position.
+    ///
+    /// If `position` is past the end of the data then
+    /// the end of the data is used instead.
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.data.len());
+    }
+
+    /// Returns the number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadCursor;
+
+    #[test]
+    fn read_and_seek() {
+        let data = [1u8, 2, 3, 4];
+        let mut cursor = ReadCursor::new(&data);
+
+        assert_eq!(cursor.read_u8(), Some(1));
+        assert_eq!(cursor.read_slice(2), Some(&data[1..3]));
+        assert_eq!(cursor.position(), 3);
+        assert_eq!(cursor.read_slice(2), None);
+        assert_eq!(cursor.remaining(), 1);
+
+        cursor.seek(1);
+        assert_eq!(cursor.read_u8(), Some(2));
+
+        cursor.seek(10);
+        assert_eq!(cursor.remaining(), 0);
+        assert_eq!(cursor.read_u8(), None);
+    }
+}
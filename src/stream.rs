@@ -0,0 +1,71 @@
+//          Copyright Andreas Wass 2004 - 2020.
+// Distributed under the Boost Software License, Version 1.0.
+//    (See accompanying file LICENSE or copy at
+//          https://www.boost.org/LICENSE_1_0.txt)
+
+use alloc::vec::Vec;
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::input_buffer::InputBuffer;
+
+/// ByteStream is a `Stream` over the data of an InputBuffer,
+/// returned by `ib.stream()`.
+///
+/// Each poll consumes all buffered data and yields it as one chunk.
+/// The stream holds the only mutable borrow of the buffer, so no data
+/// can be added while it is alive. It therefore ends, yielding `None`,
+/// once the buffer is empty instead of waiting for more data.
+pub struct ByteStream<'b, 'a> {
+    buffer: &'b mut InputBuffer<'a>,
+}
+
+impl<'b, 'a> ByteStream<'b, 'a> {
+    pub(crate) fn new(buffer: &'b mut InputBuffer<'a>) -> Self {
+        ByteStream { buffer }
+    }
+}
+
+impl Stream for ByteStream<'_, '_> {
+    type Item = Vec<u8>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let buffer = &mut self.get_mut().buffer;
+        if buffer.is_empty() {
+            return Poll::Ready(None);
+        }
+        let len = buffer.len();
+        Poll::Ready(Some(buffer.consume_to_vec(len)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::pin::Pin;
+    use core::task::{Context, Poll, Waker};
+
+    use futures_core::Stream;
+
+    use crate::input_buffer::InputBuffer;
+
+    #[test]
+    fn poll_next() {
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut buffer = [0u8; 8];
+        let mut input_buffer = InputBuffer::new(&mut buffer);
+        input_buffer.push_multiple(&[1, 2, 3]);
+
+        let mut stream = input_buffer.stream();
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some([1, 2, 3].to_vec())));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+
+        input_buffer.push_multiple(&[4, 5]);
+        let mut stream = input_buffer.stream();
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some([4, 5].to_vec())));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+    }
+}